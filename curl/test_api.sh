@@ -5,7 +5,7 @@ TEST_ACCT="test-acct-$RANDOM"
 TEST_URL="localhost:3030/account/$TEST_ACCT"
 echo "Using test account: $TEST_ACCT"
 echo "Opening an account"
-curl -i --location --request POST $TEST_URL --header 'Content-Type: application/json' --data-raw "{\"OpenAccount\": {\"account_id\": \"$TEST_ACCT\"}}"
+curl -i --location --request POST $TEST_URL --header 'Content-Type: application/json' --data-raw "{\"OpenAccount\": {\"account_id\": \"$TEST_ACCT\", \"currency\": \"USD\", \"holder_name\": \"Jane Doe\"}}"
 echo "Depositing money"
 curl -i --location --request POST $TEST_URL --header 'Content-Type: application/json' --data "@DepositMoney.json"
 echo "Withdrawing money"
@@ -15,3 +15,6 @@ curl -i --location --request POST $TEST_URL --header 'Content-Type: application/
 echo "Checking account status (calling a query)"
 curl -i --location $TEST_URL
 echo
+echo "Checking account balance"
+curl -i --location $TEST_URL/balance
+echo
@@ -21,16 +21,16 @@ function call_lambda() {
 }
 
 echo "Opening an account"
-call_lambda "{\"OpenAccount\": {\"account_id\": \"$TEST_ACCT\"}}"
+call_lambda "{\"OpenAccount\": {\"account_id\": \"$TEST_ACCT\", \"currency\": \"USD\", \"holder_name\": \"Jane Doe\"}}"
 
 echo "Depositing money"
-call_lambda "{\"DepositMoney\":{\"amount\":1000.0}}"
+call_lambda "{\"DepositMoney\":{\"amount\":1000.0,\"currency\":\"USD\"}}"
 
 echo "Withdrawing money"
-call_lambda "{\"WithdrawMoney\":{\"atm_id\":\"ATM-N468290\",\"amount\":400.0}}"
+call_lambda "{\"WithdrawMoney\":{\"atm_id\":\"ATM-N468290\",\"amount\":400.0,\"currency\":\"USD\"}}"
 
 echo "Writing a check"
-call_lambda "{\"WriteCheck\":{\"check_number\":\"1170\",\"amount\":256.28}}"
+call_lambda "{\"WriteCheck\":{\"check_number\":\"1170\",\"amount\":256.28,\"currency\":\"USD\"}}"
 
 echo "Checking account status (calling a query)"
 PAYLOAD=""
@@ -0,0 +1,10 @@
+-- The `BankAccountView` of each account as persisted by
+-- `persistence::postgres_views::PostgresViewRepository`, with the sequence of the
+-- last event applied to it.
+CREATE TABLE IF NOT EXISTS account_views
+(
+    account_id    text                               NOT NULL,
+    payload       jsonb                              NOT NULL,
+    last_sequence bigint CHECK (last_sequence >= 0) NOT NULL,
+    PRIMARY KEY (account_id)
+);
@@ -0,0 +1,103 @@
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use cqrs_es::AggregateError;
+use serde::Serialize;
+
+use crate::domain::errors::BankAccountError;
+
+// The response body for a command that could not be executed, e.g.,
+//
+//     {
+//         "status": 400,
+//         "code": "funds_not_available",
+//         "message": "funds not available"
+//     }
+//
+// Errors returned by the aggregate are the caller's to correct and are passed
+// on as they are, the details of any technical error are only logged.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ApiError {
+    pub status: u16,
+    pub code: String,
+    pub message: String,
+}
+
+impl From<AggregateError<BankAccountError>> for ApiError {
+    fn from(err: AggregateError<BankAccountError>) -> Self {
+        match err {
+            AggregateError::UserError(err) => {
+                let message = err.to_string();
+                // Repeating something that may only be done once conflicts with
+                // the current state of the account rather than being malformed.
+                let status = if message.contains("already") {
+                    StatusCode::CONFLICT
+                } else {
+                    StatusCode::BAD_REQUEST
+                };
+                Self {
+                    status: status.as_u16(),
+                    code: message.replace(' ', "_"),
+                    message,
+                }
+            }
+            AggregateError::AggregateConflict => Self {
+                status: StatusCode::CONFLICT.as_u16(),
+                code: "aggregate_conflict".to_string(),
+                message: "the account was changed concurrently, please retry".to_string(),
+            },
+            AggregateError::DatabaseConnectionError(_)
+            | AggregateError::DeserializationError(_)
+            | AggregateError::UnexpectedError(_) => Self {
+                status: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                code: "internal_error".to_string(),
+                message: "internal server error".to_string(),
+            },
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        (status, Json(self)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod api_error_tests {
+    use cqrs_es::AggregateError;
+
+    use crate::api_error::ApiError;
+    use crate::domain::errors::BankAccountError;
+
+    #[test]
+    fn test_user_error() {
+        let err = AggregateError::UserError(BankAccountError::from("funds not available"));
+        assert_eq!(
+            ApiError::from(err),
+            ApiError {
+                status: 400,
+                code: "funds_not_available".to_string(),
+                message: "funds not available".to_string(),
+            }
+        );
+
+        let err = AggregateError::UserError(BankAccountError::from("account already open"));
+        assert_eq!(ApiError::from(err).status, 409);
+    }
+
+    #[test]
+    fn test_technical_error_is_sanitized() {
+        let cause = std::io::Error::other("connection refused by db-primary-2:5432");
+        let err = AggregateError::<BankAccountError>::DatabaseConnectionError(Box::new(cause));
+        assert_eq!(
+            ApiError::from(err),
+            ApiError {
+                status: 500,
+                code: "internal_error".to_string(),
+                message: "internal server error".to_string(),
+            }
+        );
+    }
+}
@@ -0,0 +1,31 @@
+use clap::Parser;
+use cqrs_demo::cli::{AccountCtl, Cli};
+use cqrs_demo::eventstore_client::get_client;
+use cqrs_demo::persistence::eventstore_store::EventStoreDbStore;
+use cqrs_es::mem_store::MemStore;
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    let result = if cli.in_memory {
+        let ctl = AccountCtl::new(MemStore::default());
+        ctl.run(cli.command, &cli.currency).await
+    } else {
+        let client = match get_client() {
+            Ok(client) => client,
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        };
+        let ctl = AccountCtl::new(EventStoreDbStore::new(client));
+        ctl.run(cli.command, &cli.currency).await
+    };
+    match result {
+        Ok(output) => println!("{}", output),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use cqrs_es::{AggregateError, CqrsFramework, EventStore};
+
+use crate::domain::aggregate::BankAccount;
+use crate::domain::commands::BankAccountCommand;
+use crate::domain::errors::BankAccountError;
+use crate::domain::money::Money;
+
+// Moves funds between two accounts at this bank as one logical operation.
+// Each account is a separate aggregate so the transfer cannot be committed
+// atomically, instead the source is debited first and the destination credited
+// second. If the credit fails the debit is reversed and the credit's error is
+// returned, leaving both accounts as they were, e.g., when the destination holds
+// a currency other than `currency`.
+pub async fn book_transfer<ES: EventStore<BankAccount>>(
+    cqrs: &CqrsFramework<BankAccount, ES>,
+    from_account_id: &str,
+    to_account_id: String,
+    amount: Money,
+    currency: String,
+    metadata: HashMap<String, String>,
+) -> Result<(), AggregateError<BankAccountError>> {
+    let debit = BankAccountCommand::BookTransfer {
+        to_account_id: to_account_id.clone(),
+        amount,
+        currency: currency.clone(),
+    };
+    cqrs.execute_with_metadata(from_account_id, debit, metadata.clone())
+        .await?;
+
+    let credit = BankAccountCommand::ReceiveBookTransfer {
+        from_account_id: from_account_id.to_string(),
+        amount,
+        currency,
+    };
+    let err = match cqrs
+        .execute_with_metadata(&to_account_id, credit, metadata.clone())
+        .await
+    {
+        Ok(()) => return Ok(()),
+        Err(err) => err,
+    };
+
+    let reversal = BankAccountCommand::ReverseBookTransfer {
+        to_account_id,
+        amount,
+    };
+    if let Err(reversal_err) = cqrs
+        .execute_with_metadata(from_account_id, reversal, metadata)
+        .await
+    {
+        println!(
+            "Error: book transfer from {} could not be reversed: {:#?}\n",
+            from_account_id, reversal_err
+        );
+    }
+    Err(err)
+}
+
+#[cfg(test)]
+mod book_transfer_tests {
+    use std::collections::HashMap;
+
+    use cqrs_es::mem_store::MemStore;
+    use cqrs_es::{CqrsFramework, EventStore};
+
+    use crate::book_transfer::book_transfer;
+    use crate::domain::aggregate::BankAccount;
+    use crate::domain::commands::BankAccountCommand;
+    use crate::domain::events::BankAccountEvent;
+    use crate::domain::money::Money;
+    use crate::services::{BankAccountServices, HappyPathBankAccountServices};
+
+    async fn opened_account(
+        cqrs: &CqrsFramework<BankAccount, MemStore<BankAccount>>,
+        id: &str,
+        currency: &str,
+    ) {
+        let open = BankAccountCommand::OpenAccount {
+            account_id: id.to_string(),
+            currency: currency.to_string(),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
+        };
+        cqrs.execute(id, open).await.unwrap();
+        let deposit = BankAccountCommand::DepositMoney {
+            amount: Money::from_cents(50_000),
+            currency: currency.to_string(),
+        };
+        cqrs.execute(id, deposit).await.unwrap();
+    }
+
+    async fn payloads(store: &MemStore<BankAccount>, id: &str) -> Vec<BankAccountEvent> {
+        let events = store.load_events(id).await.unwrap();
+        events.into_iter().map(|event| event.payload).collect()
+    }
+
+    #[tokio::test]
+    async fn test_book_transfer() {
+        let store = MemStore::<BankAccount>::default();
+        let services = BankAccountServices::new(Box::new(HappyPathBankAccountServices));
+        let cqrs = CqrsFramework::new(store.clone(), vec![], services);
+        opened_account(&cqrs, "ACCT-1", "USD").await;
+        opened_account(&cqrs, "ACCT-2", "USD").await;
+
+        book_transfer(
+            &cqrs,
+            "ACCT-1",
+            "ACCT-2".to_string(),
+            Money::from_cents(20_000),
+            "USD".to_string(),
+            HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        let source = payloads(&store, "ACCT-1").await;
+        assert_eq!(
+            source.last().unwrap().balance(),
+            Some(Money::from_cents(30_000))
+        );
+        let destination = payloads(&store, "ACCT-2").await;
+        assert_eq!(
+            destination.last().unwrap().balance(),
+            Some(Money::from_cents(70_000))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_book_transfer_compensated_when_credit_fails() {
+        let store = MemStore::<BankAccount>::default();
+        let services = BankAccountServices::new(Box::new(HappyPathBankAccountServices));
+        let cqrs = CqrsFramework::new(store.clone(), vec![], services);
+        opened_account(&cqrs, "ACCT-1", "USD").await;
+
+        let err = book_transfer(
+            &cqrs,
+            "ACCT-1",
+            "ACCT-2".to_string(),
+            Money::from_cents(20_000),
+            "USD".to_string(),
+            HashMap::new(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.to_string(), "account not open");
+
+        let source = payloads(&store, "ACCT-1").await;
+        assert_eq!(
+            source[2..],
+            [
+                BankAccountEvent::BookTransferSent {
+                    to_account_id: "ACCT-2".to_string(),
+                    amount: Money::from_cents(20_000),
+                    balance: Money::from_cents(30_000),
+                },
+                BankAccountEvent::BookTransferReversed {
+                    to_account_id: "ACCT-2".to_string(),
+                    amount: Money::from_cents(20_000),
+                    balance: Money::from_cents(50_000),
+                },
+            ]
+        );
+        assert!(payloads(&store, "ACCT-2").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_book_transfer_between_currencies_is_rejected() {
+        let store = MemStore::<BankAccount>::default();
+        let services = BankAccountServices::new(Box::new(HappyPathBankAccountServices));
+        let cqrs = CqrsFramework::new(store.clone(), vec![], services);
+        opened_account(&cqrs, "ACCT-1", "USD").await;
+        opened_account(&cqrs, "ACCT-2", "EUR").await;
+
+        let err = book_transfer(
+            &cqrs,
+            "ACCT-1",
+            "ACCT-2".to_string(),
+            Money::from_cents(20_000),
+            "USD".to_string(),
+            HashMap::new(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.to_string(), "currency mismatch");
+
+        let source = payloads(&store, "ACCT-1").await;
+        assert_eq!(
+            source.last().unwrap().balance(),
+            Some(Money::from_cents(50_000))
+        );
+        let destination = payloads(&store, "ACCT-2").await;
+        assert_eq!(
+            destination.last().unwrap().balance(),
+            Some(Money::from_cents(50_000))
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use clap::{Parser, Subcommand};
+use cqrs_es::{AggregateError, CqrsFramework, EventEnvelope, EventStore};
+use tokio::sync::broadcast::Receiver;
+
+use crate::domain::aggregate::{BankAccount, ACCOUNT_CURRENCY};
+use crate::domain::check_number::CheckNumber;
+use crate::domain::commands::BankAccountCommand;
+use crate::domain::errors::BankAccountError;
+use crate::domain::events::BankAccountEvent;
+use crate::domain::money::Money;
+use crate::queries::{rebuild_view, BankAccountView, EventBroadcaster};
+use crate::services::{BankAccountServices, HappyPathBankAccountServices};
+
+// Withdrawals made from the command line are recorded against this ATM id.
+const CLI_ATM_ID: &str = "accountctl";
+
+// The arguments of `accountctl`, e.g.,
+//
+//     accountctl open --id ACCT-1 --holder "Jane Doe"
+//     accountctl deposit --id ACCT-1 --amount 200.00
+//     accountctl show --id ACCT-1
+#[derive(Debug, Parser)]
+#[command(
+    name = "accountctl",
+    about = "Dispatches commands to a bank account and shows its view"
+)]
+pub struct Cli {
+    #[arg(
+        long,
+        help = "Use an event store held in memory, which is empty every time accountctl is run"
+    )]
+    pub in_memory: bool,
+    #[arg(long, global = true, default_value = ACCOUNT_CURRENCY, help = "The ISO 4217 currency of the account")]
+    pub currency: String,
+    #[command(subcommand)]
+    pub command: CliCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CliCommand {
+    #[command(about = "Opens an account")]
+    Open {
+        #[arg(long)]
+        id: String,
+        #[arg(long)]
+        holder: String,
+    },
+    #[command(about = "Deposits money into an account")]
+    Deposit {
+        #[arg(long)]
+        id: String,
+        #[arg(long)]
+        amount: Money,
+    },
+    #[command(about = "Withdraws cash from an account")]
+    Withdraw {
+        #[arg(long)]
+        id: String,
+        #[arg(long)]
+        amount: Money,
+    },
+    #[command(about = "Writes a check against an account")]
+    Check {
+        #[arg(long)]
+        id: String,
+        #[arg(long, value_parser = CheckNumber::new)]
+        number: CheckNumber,
+        #[arg(long)]
+        amount: Money,
+    },
+    #[command(about = "Shows the current view of an account")]
+    Show {
+        #[arg(long)]
+        id: String,
+    },
+}
+
+// Runs the commands given to `accountctl` against an event store. A command's
+// output is the events it produced, or the view of the account for `show`, as
+// pretty printed JSON. The view is rebuilt from the account's events so that it
+// is the same whichever store is used.
+pub struct AccountCtl<S: EventStore<BankAccount> + Clone> {
+    store: S,
+    cqrs: CqrsFramework<BankAccount, S>,
+    broadcaster: EventBroadcaster,
+}
+
+impl<S: EventStore<BankAccount> + Clone> AccountCtl<S> {
+    pub fn new(store: S) -> Self {
+        let broadcaster = EventBroadcaster::new(16);
+        let services = BankAccountServices::new(Box::new(HappyPathBankAccountServices));
+        let cqrs = CqrsFramework::new(store.clone(), vec![Box::new(broadcaster.clone())], services);
+        Self {
+            store,
+            cqrs,
+            broadcaster,
+        }
+    }
+
+    pub async fn run(
+        &self,
+        command: CliCommand,
+        currency: &str,
+    ) -> Result<String, AggregateError<BankAccountError>> {
+        let currency = currency.to_string();
+        let (id, command) = match command {
+            CliCommand::Open { id, holder } => {
+                let command = BankAccountCommand::OpenAccount {
+                    account_id: id.clone(),
+                    currency,
+                    holder_name: holder,
+                    email: None,
+                };
+                (id, command)
+            }
+            CliCommand::Deposit { id, amount } => {
+                (id, BankAccountCommand::DepositMoney { amount, currency })
+            }
+            CliCommand::Withdraw { id, amount } => {
+                let command = BankAccountCommand::WithdrawMoney {
+                    amount,
+                    atm_id: CLI_ATM_ID.to_string(),
+                    authorized_by: None,
+                    currency,
+                };
+                (id, command)
+            }
+            CliCommand::Check { id, number, amount } => {
+                let command = BankAccountCommand::WriteCheck {
+                    check_number: number,
+                    amount,
+                    payee: None,
+                    currency,
+                };
+                (id, command)
+            }
+            CliCommand::Show { id } => {
+                let view: BankAccountView = rebuild_view(&self.store, &id).await?;
+                return Ok(to_json(&view));
+            }
+        };
+        let mut receiver = self.broadcaster.subscribe();
+        self.cqrs.execute(&id, command).await?;
+        Ok(to_json(&received_events(&mut receiver)))
+    }
+}
+
+// The events broadcast to `receiver` so far, which once a command has been
+// executed are those it produced.
+fn received_events(receiver: &mut Receiver<EventEnvelope<BankAccount>>) -> Vec<BankAccountEvent> {
+    let mut events = Vec::new();
+    while let Ok(event) = receiver.try_recv() {
+        events.push(event.payload);
+    }
+    events
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).expect("views and events serialize to JSON")
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use clap::Parser;
+    use cqrs_es::mem_store::MemStore;
+    use serde_json::Value;
+
+    use crate::cli::{AccountCtl, Cli};
+    use crate::domain::aggregate::BankAccount;
+
+    async fn run(ctl: &AccountCtl<MemStore<BankAccount>>, args: &[&str]) -> Result<String, String> {
+        let cli = Cli::parse_from(["accountctl", "--in-memory"].iter().chain(args));
+        ctl.run(cli.command, &cli.currency)
+            .await
+            .map_err(|err| err.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_show_after_commands() {
+        let ctl = AccountCtl::new(MemStore::<BankAccount>::default());
+        run(&ctl, &["open", "--id", "ACCT-1", "--holder", "Jane Doe"])
+            .await
+            .unwrap();
+        let deposited = run(&ctl, &["deposit", "--id", "ACCT-1", "--amount", "200.00"])
+            .await
+            .unwrap();
+        let deposited: Value = serde_json::from_str(&deposited).unwrap();
+        assert_eq!(deposited[0]["CustomerDepositedMoney"]["balance"], "200.00");
+        run(
+            &ctl,
+            &[
+                "check", "--id", "ACCT-1", "--number", "1170", "--amount", "75.00",
+            ],
+        )
+        .await
+        .unwrap();
+
+        let shown = run(&ctl, &["show", "--id", "ACCT-1"]).await.unwrap();
+        let view: Value = serde_json::from_str(&shown).unwrap();
+        assert_eq!(view["account_id"], "ACCT-1");
+        assert_eq!(view["holder_name"], "Jane Doe");
+        assert_eq!(view["balance"], "125.00");
+        assert_eq!(view["written_checks"], serde_json::json!(["1170"]));
+    }
+
+    #[tokio::test]
+    async fn test_domain_error() {
+        let ctl = AccountCtl::new(MemStore::<BankAccount>::default());
+        run(&ctl, &["open", "--id", "ACCT-1", "--holder", "Jane Doe"])
+            .await
+            .unwrap();
+
+        let result = run(&ctl, &["withdraw", "--id", "ACCT-1", "--amount", "50.00"]).await;
+        assert_eq!(result, Err("funds not available".to_string()));
+    }
+}
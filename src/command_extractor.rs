@@ -12,6 +12,7 @@ use std::collections::HashMap;
 pub struct CommandExtractor(pub HashMap<String, String>, pub BankAccountCommand);
 
 const USER_AGENT_HDR: &str = "User-Agent";
+const CORRELATION_ID_HDR: &str = "X-Correlation-Id";
 
 #[async_trait]
 impl<S, B> FromRequest<S, B> for CommandExtractor
@@ -24,8 +25,8 @@ where
     type Rejection = CommandExtractionError;
 
     async fn from_request(req: Request<B>, state: &S) -> Result<Self, Self::Rejection> {
-        // Here we are including the current date/time, the uri that was called and the user-agent
-        // in a HashMap that we will submit as metadata with the command.
+        // Here we are including the current date/time, the uri that was called, the user-agent
+        // and any correlation id in a HashMap that we will submit as metadata with the command.
         let mut metadata = HashMap::default();
         metadata.insert("time".to_string(), chrono::Utc::now().to_rfc3339());
         metadata.insert("uri".to_string(), req.uri().to_string());
@@ -34,6 +35,12 @@ where
                 metadata.insert(USER_AGENT_HDR.to_string(), value.to_string());
             }
         }
+        // A caller-supplied correlation id is carried through to the event store.
+        if let Some(correlation_id) = req.headers().get(CORRELATION_ID_HDR) {
+            if let Ok(value) = correlation_id.to_str() {
+                metadata.insert("correlation_id".to_string(), value.to_string());
+            }
+        }
 
         // Parse and deserialize the request body as the command payload.
         let body = Bytes::from_request(req, state).await?;
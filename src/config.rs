@@ -5,9 +5,27 @@ use postgres_es::{PostgresCqrs, PostgresViewRepository};
 use sqlx::{Pool, Postgres};
 
 use crate::domain::aggregate::BankAccount;
-use crate::queries::{AccountQuery, BankAccountView, SimpleLoggingQuery};
+use crate::domain::money::Money;
+use crate::queries::{
+    AccountNotesQuery, AccountQuery, ActivationQuery, AlertingQuery, AtmLocationQuery,
+    BankAccountView, DeduplicatingQuery, FeeBurdenQuery, FlaggedTransactionsQuery, FlowTimingQuery,
+    GapQuery, GoalProjectionQuery, LoggingComplianceAlertNotifier, LoggingSpendAlertNotifier,
+    PayeeQuery, RegulatorySnapshotQuery, SequenceBalanceQuery, SimpleLoggingQuery, SnapshotPeriod,
+    SpendAlertQuery, TAccountQuery, TimeWeightedReturnQuery, TransactionHistoryQuery,
+    VolatilityQuery, WeekdayActivityQuery,
+};
 use crate::services::{BankAccountServices, HappyPathBankAccountServices};
 
+// Single transactions above this amount are reported to compliance.
+const COMPLIANCE_ALERT_THRESHOLD: Money = Money::from_cents(1_000_000);
+
+// The reporting period of the balances recorded by the regulatory snapshot query.
+const REGULATORY_SNAPSHOT_PERIOD: SnapshotPeriod = SnapshotPeriod::Quarterly;
+
+// How many accounts the queries without a view remember the last dispatched
+// sequence of, to drop replayed events.
+const DEDUPLICATED_ACCOUNTS: usize = 10_000;
+
 pub fn cqrs_framework(
     pool: Pool<Postgres>,
 ) -> (
@@ -26,9 +44,163 @@ pub fn cqrs_framework(
     // Consider logging an error or panicking in your own application.
     account_query.use_error_handler(Box::new(|e| println!("{}", e)));
 
+    // A query that tracks the time from opening an account to its first deposit.
+    let activation_view_repo = Arc::new(PostgresViewRepository::new(
+        "activation_query",
+        pool.clone(),
+    ));
+    let mut activation_query = ActivationQuery::new(activation_view_repo);
+    activation_query.use_error_handler(Box::new(|e| println!("{}", e)));
+
+    // A query that presents the account as debit and credit columns.
+    let t_account_view_repo =
+        Arc::new(PostgresViewRepository::new("t_account_query", pool.clone()));
+    let mut t_account_query = TAccountQuery::new(t_account_view_repo);
+    t_account_query.use_error_handler(Box::new(|e| println!("{}", e)));
+
+    // A query that stores the notes support agents have added to an account.
+    let account_notes_view_repo = Arc::new(PostgresViewRepository::new(
+        "account_notes_query",
+        pool.clone(),
+    ));
+    let mut account_notes_query = AccountNotesQuery::new(account_notes_view_repo);
+    account_notes_query.use_error_handler(Box::new(|e| println!("{}", e)));
+
+    // A query that records period-end balances for regulatory reporting.
+    let regulatory_snapshot_view_repo = Arc::new(PostgresViewRepository::new(
+        "regulatory_snapshot_query",
+        pool.clone(),
+    ));
+    let mut regulatory_snapshot_query =
+        RegulatorySnapshotQuery::new(regulatory_snapshot_view_repo, REGULATORY_SNAPSHOT_PERIOD);
+    regulatory_snapshot_query.use_error_handler(Box::new(|e| println!("{}", e)));
+
+    // A query that counts transactions by day of the week.
+    let weekday_activity_view_repo = Arc::new(PostgresViewRepository::new(
+        "weekday_activity_query",
+        pool.clone(),
+    ));
+    let mut weekday_activity_query = WeekdayActivityQuery::new(weekday_activity_view_repo);
+    weekday_activity_query.use_error_handler(Box::new(|e| println!("{}", e)));
+
+    // A query that tracks the longest period without any transactions.
+    let gap_view_repo = Arc::new(PostgresViewRepository::new("gap_query", pool.clone()));
+    let mut gap_query = GapQuery::new(gap_view_repo);
+    gap_query.use_error_handler(Box::new(|e| println!("{}", e)));
+
+    // A query that totals the amounts paid by check to each payee.
+    let payee_view_repo = Arc::new(PostgresViewRepository::new("payee_query", pool.clone()));
+    let mut payee_query = PayeeQuery::new(payee_view_repo);
+    payee_query.use_error_handler(Box::new(|e| println!("{}", e)));
+
+    // A query that measures how long deposited funds are held before withdrawal.
+    let flow_timing_view_repo = Arc::new(PostgresViewRepository::new(
+        "flow_timing_query",
+        pool.clone(),
+    ));
+    let mut flow_timing_query = FlowTimingQuery::new(flow_timing_view_repo);
+    flow_timing_query.use_error_handler(Box::new(|e| println!("{}", e)));
+
+    // A query that records daily closing balances to measure their volatility.
+    let volatility_view_repo = Arc::new(PostgresViewRepository::new(
+        "volatility_query",
+        pool.clone(),
+    ));
+    let mut volatility_query = VolatilityQuery::new(volatility_view_repo);
+    volatility_query.use_error_handler(Box::new(|e| println!("{}", e)));
+
+    // A query that computes the time-weighted return of the account.
+    let time_weighted_return_view_repo = Arc::new(PostgresViewRepository::new(
+        "time_weighted_return_query",
+        pool.clone(),
+    ));
+    let mut time_weighted_return_query =
+        TimeWeightedReturnQuery::new(time_weighted_return_view_repo);
+    time_weighted_return_query.use_error_handler(Box::new(|e| println!("{}", e)));
+
+    // A query that lists the transactions flagged for AML review.
+    let flagged_transactions_view_repo = Arc::new(PostgresViewRepository::new(
+        "flagged_transactions_query",
+        pool.clone(),
+    ));
+    let mut flagged_transactions_query =
+        FlaggedTransactionsQuery::new(flagged_transactions_view_repo);
+    flagged_transactions_query.use_error_handler(Box::new(|e| println!("{}", e)));
+
+    // A query that records the balance at every sequence for point-in-time lookups.
+    let sequence_balance_view_repo = Arc::new(PostgresViewRepository::new(
+        "sequence_balance_query",
+        pool.clone(),
+    ));
+    let mut sequence_balance_query = SequenceBalanceQuery::new(sequence_balance_view_repo);
+    sequence_balance_query.use_error_handler(Box::new(|e| println!("{}", e)));
+
+    // A query that compares the fees charged each month to the account's balance.
+    let fee_burden_view_repo = Arc::new(PostgresViewRepository::new(
+        "fee_burden_query",
+        pool.clone(),
+    ));
+    let mut fee_burden_query = FeeBurdenQuery::new(fee_burden_view_repo);
+    fee_burden_query.use_error_handler(Box::new(|e| println!("{}", e)));
+
+    // A query that projects when the balance will reach a savings goal.
+    let goal_projection_view_repo = Arc::new(PostgresViewRepository::new(
+        "goal_projection_query",
+        pool.clone(),
+    ));
+    let mut goal_projection_query = GoalProjectionQuery::new(goal_projection_view_repo);
+    goal_projection_query.use_error_handler(Box::new(|e| println!("{}", e)));
+
+    // A query that keeps the locations of recent ATM withdrawals.
+    let atm_location_view_repo = Arc::new(PostgresViewRepository::new(
+        "atm_location_query",
+        pool.clone(),
+    ));
+    let mut atm_location_query = AtmLocationQuery::new(atm_location_view_repo);
+    atm_location_query.use_error_handler(Box::new(|e| println!("{}", e)));
+
+    // A query that keeps a statement of the account's transactions.
+    let transaction_history_view_repo = Arc::new(PostgresViewRepository::new(
+        "transaction_history_query",
+        pool.clone(),
+    ));
+    let mut transaction_history_query = TransactionHistoryQuery::new(transaction_history_view_repo);
+    transaction_history_query.use_error_handler(Box::new(|e| println!("{}", e)));
+
     // Create and return an event-sourced `CqrsFramework`.
-    let queries: Vec<Box<dyn Query<BankAccount>>> =
-        vec![Box::new(simple_query), Box::new(account_query)];
+    // Each view records the last sequence it applied and skips a replayed event
+    // itself; the queries without a view are guarded from one here instead.
+    let queries: Vec<Box<dyn Query<BankAccount>>> = vec![
+        Box::new(DeduplicatingQuery::new(simple_query, DEDUPLICATED_ACCOUNTS)),
+        Box::new(account_query),
+        Box::new(activation_query),
+        Box::new(t_account_query),
+        Box::new(account_notes_query),
+        Box::new(regulatory_snapshot_query),
+        Box::new(weekday_activity_query),
+        Box::new(gap_query),
+        Box::new(payee_query),
+        Box::new(atm_location_query),
+        Box::new(flow_timing_query),
+        Box::new(volatility_query),
+        Box::new(time_weighted_return_query),
+        Box::new(flagged_transactions_query),
+        Box::new(sequence_balance_query),
+        Box::new(fee_burden_query),
+        Box::new(goal_projection_query),
+        Box::new(transaction_history_query),
+        Box::new(DeduplicatingQuery::new(
+            SpendAlertQuery::new(Box::new(LoggingSpendAlertNotifier)),
+            DEDUPLICATED_ACCOUNTS,
+        )),
+        Box::new(DeduplicatingQuery::new(
+            AlertingQuery::new(
+                COMPLIANCE_ALERT_THRESHOLD,
+                Box::new(LoggingComplianceAlertNotifier),
+            ),
+            DEDUPLICATED_ACCOUNTS,
+        )),
+    ];
     let services = BankAccountServices::new(Box::new(HappyPathBankAccountServices));
     (
         Arc::new(postgres_es::postgres_cqrs(pool, queries, services)),
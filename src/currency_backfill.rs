@@ -0,0 +1,76 @@
+use cqrs_es::{AggregateError, CqrsFramework, EventStore};
+
+use crate::domain::aggregate::{BankAccount, ACCOUNT_CURRENCY};
+use crate::domain::commands::BankAccountCommand;
+use crate::domain::errors::BankAccountError;
+
+// A one-time migration for accounts opened before their currency was recorded.
+// Rather than rewriting the legacy `AccountOpened` events, a `CurrencyBackfilled`
+// event is appended to each account's stream. An account that already has a
+// currency ignores the backfill so the migration can safely be run again, e.g.,
+// after a partial failure.
+pub async fn backfill_currency<ES: EventStore<BankAccount>>(
+    cqrs: &CqrsFramework<BankAccount, ES>,
+    account_ids: &[String],
+) -> Result<(), AggregateError<BankAccountError>> {
+    for account_id in account_ids {
+        let command = BankAccountCommand::BackfillCurrency {
+            currency: ACCOUNT_CURRENCY.to_string(),
+        };
+        cqrs.execute(account_id, command).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod currency_backfill_tests {
+    use cqrs_es::mem_store::MemStore;
+    use cqrs_es::{CqrsFramework, EventStore};
+    use std::collections::HashMap;
+
+    use crate::currency_backfill::backfill_currency;
+    use crate::domain::aggregate::BankAccount;
+    use crate::domain::events::BankAccountEvent;
+    use crate::services::{BankAccountServices, HappyPathBankAccountServices};
+
+    #[tokio::test]
+    async fn test_backfill_is_idempotent() {
+        let store = MemStore::<BankAccount>::default();
+        let services = BankAccountServices::new(Box::new(HappyPathBankAccountServices));
+        let cqrs = CqrsFramework::new(store.clone(), vec![], services);
+        // An account opened before its currency was recorded.
+        let legacy_open = BankAccountEvent::AccountOpened {
+            account_id: "ACCT-1".to_string(),
+            currency: None,
+            holder_name: String::new(),
+            email: None,
+        };
+        let context = store.load_aggregate("ACCT-1").await.unwrap();
+        store
+            .commit(vec![legacy_open], context, HashMap::new())
+            .await
+            .unwrap();
+
+        let account_ids = vec!["ACCT-1".to_string()];
+        backfill_currency(&cqrs, &account_ids).await.unwrap();
+        backfill_currency(&cqrs, &account_ids).await.unwrap();
+
+        let events = store.load_events("ACCT-1").await.unwrap();
+        let payloads: Vec<BankAccountEvent> =
+            events.into_iter().map(|event| event.payload).collect();
+        assert_eq!(
+            payloads,
+            vec![
+                BankAccountEvent::AccountOpened {
+                    account_id: "ACCT-1".to_string(),
+                    currency: None,
+                    holder_name: String::new(),
+                    email: None,
+                },
+                BankAccountEvent::CurrencyBackfilled {
+                    currency: "USD".to_string(),
+                },
+            ]
+        );
+    }
+}
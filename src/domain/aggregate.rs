@@ -1,15 +1,101 @@
 use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
 use cqrs_es::Aggregate;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use uuid::Uuid;
 
+use crate::domain::check_number::CheckNumber;
 use crate::domain::commands::BankAccountCommand;
-use crate::domain::events::{BankAccountError, BankAccountEvent};
+use crate::domain::errors::BankAccountError;
+use crate::domain::events::{BankAccountEvent, KycStatus, StatementChannel};
+use crate::domain::money::Money;
 use crate::services::BankAccountServices;
 
-#[derive(Serialize, Deserialize)]
+// The currency of accounts opened before the currency of each account was
+// recorded.
+pub const ACCOUNT_CURRENCY: &str = "USD";
+
+// Withdrawals above this amount from a joint account require an authorizing joint holder.
+const JOINT_AUTHORIZATION_THRESHOLD: Money = Money::from_cents(100_000);
+
+// Deposits and withdrawals above this amount require a verified account holder.
+const KYC_THRESHOLD: Money = Money::from_cents(1_000_000);
+
+// The number of the most recent command ids remembered to recognize a retried command.
+const RECENT_COMMAND_IDS: usize = 100;
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct BankAccount {
+    opened: bool,
     account_id: String,
-    balance: f64,
+    holder_name: String,
+    email: Option<String>,
+    balance: Money,
+    pending_adjustments: HashMap<String, PendingAdjustment>,
+    // Loan-sourced funds are tracked separately from the customer's own deposits.
+    disbursed_loans: HashSet<String>,
+    loan_funds: Money,
+    paperless: bool,
+    joint_holders: HashSet<String>,
+    checks_written: usize,
+    // The amount of each check written, by check number, so that it can be voided.
+    written_checks: HashMap<CheckNumber, Money>,
+    voided_checks: HashSet<CheckNumber>,
+    overdraft_limit: Money,
+    overdraft_fee: Money,
+    // Mobile check deposits are credited to the balance but held until they clear,
+    // they cannot be withdrawn in the meantime.
+    pending_deposits: Money,
+    // The amount of each check deposit held, by the reference of its front image.
+    pending_checks: HashMap<String, Money>,
+    spend_alert_threshold: Option<Money>,
+    low_balance_threshold: Option<Money>,
+    scheduled_payments: Vec<ScheduledPayment>,
+    // Returned direct debits awaiting retry, by mandate.
+    direct_debit_retries: BTreeMap<String, DirectDebitRetry>,
+    // The number of events applied, i.e., the sequence of the latest event.
+    event_count: usize,
+    flagged_sequences: BTreeSet<usize>,
+    kyc_status: Option<KycStatus>,
+    // The cash withdrawn on the day of the latest withdrawal.
+    daily_withdrawn: Money,
+    last_withdrawal_date: Option<NaiveDate>,
+    closed: bool,
+    // Not recorded for accounts opened before currencies were, until backfilled.
+    currency: Option<String>,
+    // The ids of the latest `Idempotent` commands processed, oldest first.
+    recent_command_ids: VecDeque<Uuid>,
+    // Book transfers sent that have not been reversed, only these can be.
+    sent_transfers: Vec<SentTransfer>,
+}
+
+// A reconciliation adjustment that has been proposed but not yet approved,
+// it does not affect the balance until a second user approves it.
+#[derive(Clone, Serialize, Deserialize)]
+struct PendingAdjustment {
+    proposer: String,
+    delta: Money,
+}
+
+// A bill payment that has been scheduled but not yet processed.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct ScheduledPayment {
+    payee_id: String,
+    amount: Money,
+    pay_date: NaiveDate,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+struct SentTransfer {
+    to_account_id: String,
+    amount: Money,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct DirectDebitRetry {
+    amount: Money,
+    retry_date: NaiveDate,
 }
 
 #[async_trait]
@@ -24,285 +110,3887 @@ impl Aggregate for BankAccount {
         "account".to_string()
     }
 
-    // The aggregate logic goes here. Note that this will be the _bulk_ of a CQRS system
-    // so expect to use helper functions elsewhere to keep the code clean.
-    async fn handle(
-        &self,
-        command: Self::Command,
-        services: &Self::Services,
-    ) -> Result<Vec<Self::Event>, Self::Error> {
-        match command {
-            BankAccountCommand::OpenAccount { account_id } => {
-                Ok(vec![BankAccountEvent::AccountOpened { account_id }])
-            }
-            BankAccountCommand::DepositMoney { amount } => {
-                let balance = self.balance + amount;
-                Ok(vec![BankAccountEvent::CustomerDepositedMoney {
-                    amount,
-                    balance,
-                }])
-            }
-            BankAccountCommand::WithdrawMoney { amount, atm_id } => {
-                let balance = self.balance - amount;
-                if balance < 0_f64 {
-                    return Err("funds not available".into());
-                }
-                if services
-                    .services
-                    .atm_withdrawal(&atm_id, amount)
-                    .await
-                    .is_err()
-                {
-                    return Err("atm rule violation".into());
-                };
-                Ok(vec![BankAccountEvent::CustomerWithdrewCash {
-                    amount,
-                    balance,
-                }])
-            }
-            BankAccountCommand::WriteCheck {
-                check_number,
-                amount,
-            } => {
-                let balance = self.balance - amount;
-                if balance < 0_f64 {
-                    return Err("funds not available".into());
-                }
-                if services
-                    .services
-                    .validate_check(&self.account_id, &check_number)
-                    .await
-                    .is_err()
-                {
-                    return Err("check invalid".into());
-                };
-                Ok(vec![BankAccountEvent::CustomerWroteCheck {
-                    check_number,
-                    amount,
-                    balance,
-                }])
-            }
-            BankAccountCommand::IssueBonus {  bonus_id, amount,} => {
-                let balance = self.balance + amount;
-                Ok(vec![BankAccountEvent::IssuedBonus{
-                    amount,
-                    bonus_id,
-                    balance
-                }])
-            }
-            
-        }
+    // The aggregate logic goes here. Note that this will be the _bulk_ of a CQRS system
+    // so expect to use helper functions elsewhere to keep the code clean.
+    async fn handle(
+        &self,
+        command: Self::Command,
+        services: &Self::Services,
+    ) -> Result<Vec<Self::Event>, Self::Error> {
+        match command {
+            BankAccountCommand::OpenAccount {
+                account_id,
+                currency,
+                holder_name,
+                email,
+            } => {
+                if self.opened {
+                    // A retry of an open that already succeeded is not an error,
+                    // opening the same account again with a different id is.
+                    if self.account_id == account_id {
+                        return Ok(vec![]);
+                    }
+                    return Err("account already open".into());
+                }
+                if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+                    return Err("invalid currency".into());
+                }
+                if holder_name.trim().is_empty() {
+                    return Err("holder name required".into());
+                }
+                if email.as_ref().is_some_and(|email| !email.contains('@')) {
+                    return Err("invalid email".into());
+                }
+                Ok(vec![BankAccountEvent::AccountOpened {
+                    account_id,
+                    currency: Some(currency),
+                    holder_name,
+                    email,
+                }])
+            }
+            BankAccountCommand::DepositMoney { amount, currency } => {
+                self.require_opened()?;
+                self.require_not_closed()?;
+                self.require_currency(&currency)?;
+                if !amount.is_positive() {
+                    return Err(BankAccountError::InvalidAmount);
+                }
+                self.require_kyc(amount)?;
+                let balance = self.credit(amount)?;
+                Ok(vec![BankAccountEvent::CustomerDepositedMoney {
+                    amount,
+                    balance,
+                }])
+            }
+            BankAccountCommand::DepositCheckImage {
+                amount,
+                front_image_ref,
+                back_image_ref,
+                currency,
+            } => {
+                self.require_opened()?;
+                self.require_not_closed()?;
+                self.require_currency(&currency)?;
+                if !amount.is_positive() {
+                    return Err(BankAccountError::InvalidAmount);
+                }
+                if front_image_ref.is_empty() || back_image_ref.is_empty() {
+                    return Err("check images required".into());
+                }
+                if self.pending_checks.contains_key(&front_image_ref) {
+                    return Err("check already deposited".into());
+                }
+                self.require_kyc(amount)?;
+                let balance = self.credit(amount)?;
+                Ok(vec![BankAccountEvent::CheckImageDeposited {
+                    amount,
+                    balance,
+                    front_image_ref,
+                    back_image_ref,
+                }])
+            }
+            BankAccountCommand::ClearCheckDeposit { front_image_ref } => {
+                let amount = self.pending_check(&front_image_ref)?;
+                Ok(vec![BankAccountEvent::CheckDepositCleared {
+                    front_image_ref,
+                    amount,
+                }])
+            }
+            BankAccountCommand::RejectCheckDeposit {
+                front_image_ref,
+                reason,
+            } => {
+                let amount = self.pending_check(&front_image_ref)?;
+                let balance = self
+                    .balance
+                    .checked_sub(amount)
+                    .ok_or(BankAccountError::AmountOutOfRange)?;
+                Ok(vec![BankAccountEvent::CheckDepositRejected {
+                    front_image_ref,
+                    amount,
+                    balance,
+                    reason,
+                }])
+            }
+            BankAccountCommand::WithdrawMoney {
+                amount,
+                atm_id,
+                authorized_by,
+                currency,
+            } => {
+                self.require_opened()?;
+                self.require_not_closed()?;
+                self.require_currency(&currency)?;
+                let (balance, overdraft_fee) = self.debit_with_overdraft(amount)?;
+                self.require_kyc(amount)?;
+                self.authorize_joint_withdrawal(amount, authorized_by)?;
+                let at = (services.clock)();
+                self.require_daily_withdrawal_limit(amount, at, services)?;
+                if services
+                    .services
+                    .atm_withdrawal(&atm_id, amount)
+                    .await
+                    .is_err()
+                {
+                    return Err("atm rule violation".into());
+                };
+                let mut events = vec![BankAccountEvent::CustomerWithdrewCash {
+                    amount,
+                    balance,
+                    at,
+                }];
+                events.extend(overdraft_fee);
+                events.extend(self.low_balance_warning(&events));
+                events.extend(self.spend_alert(amount));
+                Ok(events)
+            }
+            BankAccountCommand::WithdrawAtAtm {
+                amount,
+                atm_id,
+                location,
+                authorized_by,
+            } => {
+                self.require_opened()?;
+                self.require_not_closed()?;
+                let balance = self.debit(amount)?;
+                self.require_kyc(amount)?;
+                self.authorize_joint_withdrawal(amount, authorized_by)?;
+                let at = (services.clock)();
+                self.require_daily_withdrawal_limit(amount, at, services)?;
+                if services
+                    .services
+                    .atm_withdrawal(&atm_id, amount)
+                    .await
+                    .is_err()
+                {
+                    return Err("atm rule violation".into());
+                };
+                let mut events = vec![BankAccountEvent::AtmWithdrawal {
+                    amount,
+                    balance,
+                    atm_id,
+                    location,
+                    at,
+                }];
+                events.extend(self.spend_alert(amount));
+                Ok(events)
+            }
+            BankAccountCommand::WithdrawWithCashback {
+                purchase_amount,
+                cashback_amount,
+                merchant,
+            } => {
+                self.require_opened()?;
+                self.require_not_closed()?;
+                if !cashback_amount.is_positive() {
+                    return Err("cashback amount must be positive".into());
+                }
+                let total = purchase_amount
+                    .checked_add(cashback_amount)
+                    .ok_or(BankAccountError::AmountOutOfRange)?;
+                let balance = self.debit(total)?;
+                let at = (services.clock)();
+                self.require_daily_withdrawal_limit(cashback_amount, at, services)?;
+                let mut events = vec![BankAccountEvent::PurchaseWithCashback {
+                    purchase_amount,
+                    cashback_amount,
+                    merchant,
+                    balance,
+                    at,
+                }];
+                events.extend(self.spend_alert(total));
+                Ok(events)
+            }
+            BankAccountCommand::WriteCheck {
+                check_number,
+                amount,
+                payee,
+                currency,
+            } => {
+                self.require_opened()?;
+                self.require_not_closed()?;
+                self.require_currency(&currency)?;
+                // A command deserialized from a request has not been validated.
+                CheckNumber::new(check_number.as_str())?;
+                // A check number identifies the check once written, even if voided.
+                if self.written_checks.contains_key(&check_number) {
+                    return Err("check number already used".into());
+                }
+                let (balance, overdraft_fee) = self.debit_with_overdraft(amount)?;
+                if services
+                    .services
+                    .validate_check(&self.account_id, check_number.as_str())
+                    .await
+                    .is_err()
+                {
+                    return Err("check invalid".into());
+                };
+                let mut events = vec![BankAccountEvent::CustomerWroteCheck {
+                    check_number,
+                    amount,
+                    balance,
+                    payee,
+                }];
+                events.extend(overdraft_fee);
+                events.extend(self.low_balance_warning(&events));
+                events.extend(self.spend_alert(amount));
+                Ok(events)
+            }
+            BankAccountCommand::IssueBonus {
+                bonus_id,
+                amount,
+                currency,
+            } => {
+                self.require_opened()?;
+                self.require_not_closed()?;
+                self.require_currency(&currency)?;
+                let balance = self.credit(amount)?;
+                Ok(vec![BankAccountEvent::IssuedBonus {
+                    amount,
+                    bonus_id,
+                    balance,
+                }])
+            }
+            BankAccountCommand::ProposeReconciliationAdjustment {
+                proposal_id,
+                proposer,
+                delta,
+                reason,
+            } => {
+                if self.pending_adjustments.contains_key(&proposal_id) {
+                    return Err("adjustment already proposed".into());
+                }
+                Ok(vec![BankAccountEvent::AdjustmentProposed {
+                    proposal_id,
+                    proposer,
+                    delta,
+                    reason,
+                }])
+            }
+            BankAccountCommand::ApproveReconciliationAdjustment {
+                proposal_id,
+                approver,
+            } => {
+                self.require_not_closed()?;
+                let adjustment = match self.pending_adjustments.get(&proposal_id) {
+                    Some(adjustment) => adjustment,
+                    None => return Err("adjustment not found".into()),
+                };
+                // Adjustments require dual approval.
+                if adjustment.proposer == approver {
+                    return Err("adjustment cannot be approved by its proposer".into());
+                }
+                let delta = adjustment.delta;
+                let balance = self.credit(delta)?;
+                Ok(vec![BankAccountEvent::AdjustmentApproved {
+                    proposal_id,
+                    approver,
+                    delta,
+                    balance,
+                }])
+            }
+            BankAccountCommand::DisburseLoan {
+                loan_id,
+                amount,
+                currency,
+            } => {
+                self.require_opened()?;
+                self.require_not_closed()?;
+                self.require_currency(&currency)?;
+                if self.disbursed_loans.contains(&loan_id) {
+                    return Err("loan already disbursed".into());
+                }
+                let balance = self.credit(amount)?;
+                Ok(vec![BankAccountEvent::LoanDisbursed {
+                    loan_id,
+                    amount,
+                    balance,
+                }])
+            }
+            BankAccountCommand::AddAccountNote { note } => {
+                if note.trim().is_empty() {
+                    return Err("note cannot be empty".into());
+                }
+                Ok(vec![BankAccountEvent::AccountNoteAdded {
+                    note,
+                    timestamp: (services.clock)(),
+                }])
+            }
+            BankAccountCommand::AddJointHolder { holder_id } => {
+                if self.joint_holders.contains(&holder_id) {
+                    return Err("joint holder already added".into());
+                }
+                Ok(vec![BankAccountEvent::JointHolderAdded { holder_id }])
+            }
+            BankAccountCommand::EnrollPaperless => {
+                if self.paperless {
+                    return Err("already enrolled in paperless statements".into());
+                }
+                Ok(vec![BankAccountEvent::PaperlessEnrolled])
+            }
+            BankAccountCommand::DisenrollPaperless => {
+                if !self.paperless {
+                    return Err("not enrolled in paperless statements".into());
+                }
+                Ok(vec![BankAccountEvent::PaperlessDisenrolled])
+            }
+            BankAccountCommand::RecordStatementDelivery {
+                statement_id,
+                channel,
+            } => {
+                let channel = channel.unwrap_or(if self.paperless {
+                    StatementChannel::Email
+                } else {
+                    StatementChannel::Mail
+                });
+                Ok(vec![BankAccountEvent::StatementDelivered {
+                    statement_id,
+                    channel,
+                }])
+            }
+            BankAccountCommand::RecordKyc {
+                status,
+                verified_at,
+            } => Ok(vec![BankAccountEvent::KycStatusRecorded {
+                status,
+                verified_at,
+            }]),
+            BankAccountCommand::SetSpendAlert { threshold } => {
+                if threshold.is_negative() {
+                    return Err("spend alert threshold cannot be negative".into());
+                }
+                Ok(vec![BankAccountEvent::SpendAlertSet { threshold }])
+            }
+            BankAccountCommand::SetLowBalanceThreshold { threshold } => {
+                if threshold.is_negative() {
+                    return Err("low balance threshold cannot be negative".into());
+                }
+                Ok(vec![BankAccountEvent::LowBalanceThresholdSet { threshold }])
+            }
+            BankAccountCommand::SendWire {
+                amount,
+                beneficiary,
+                fee,
+            } => {
+                self.require_opened()?;
+                self.require_not_closed()?;
+                if (services.clock)().time() >= services.wire_cutoff {
+                    return Err("wire cutoff has passed for today".into());
+                }
+                let total = amount
+                    .checked_add(fee)
+                    .ok_or(BankAccountError::AmountOutOfRange)?;
+                let balance = self.debit(total)?;
+                let mut events = vec![BankAccountEvent::WireSent {
+                    amount,
+                    fee,
+                    balance,
+                    beneficiary,
+                }];
+                events.extend(self.spend_alert(amount));
+                Ok(events)
+            }
+            BankAccountCommand::BookTransfer {
+                to_account_id,
+                amount,
+                currency,
+            } => {
+                self.require_opened()?;
+                self.require_not_closed()?;
+                self.require_currency(&currency)?;
+                if to_account_id == self.account_id {
+                    return Err("cannot transfer to the same account".into());
+                }
+                let balance = self.debit(amount)?;
+                let mut events = vec![BankAccountEvent::BookTransferSent {
+                    to_account_id,
+                    amount,
+                    balance,
+                }];
+                events.extend(self.spend_alert(amount));
+                Ok(events)
+            }
+            BankAccountCommand::ReceiveBookTransfer {
+                from_account_id,
+                amount,
+                currency,
+            } => {
+                self.require_opened()?;
+                self.require_not_closed()?;
+                self.require_currency(&currency)?;
+                if !amount.is_positive() {
+                    return Err(BankAccountError::InvalidAmount);
+                }
+                let balance = self.credit(amount)?;
+                Ok(vec![BankAccountEvent::BookTransferReceived {
+                    from_account_id,
+                    amount,
+                    balance,
+                }])
+            }
+            BankAccountCommand::ReverseBookTransfer {
+                to_account_id,
+                amount,
+            } => {
+                self.require_not_closed()?;
+                if !amount.is_positive() {
+                    return Err(BankAccountError::InvalidAmount);
+                }
+                let sent = SentTransfer {
+                    to_account_id: to_account_id.clone(),
+                    amount,
+                };
+                if !self.sent_transfers.contains(&sent) {
+                    return Err("book transfer not found".into());
+                }
+                let balance = self.credit(amount)?;
+                Ok(vec![BankAccountEvent::BookTransferReversed {
+                    to_account_id,
+                    amount,
+                    balance,
+                }])
+            }
+            BankAccountCommand::FlagSuspicious {
+                target_sequence,
+                reason,
+            } => {
+                if target_sequence == 0 || target_sequence > self.event_count {
+                    return Err("transaction not found".into());
+                }
+                if self.flagged_sequences.contains(&target_sequence) {
+                    return Err("transaction already flagged".into());
+                }
+                Ok(vec![BankAccountEvent::TransactionFlagged {
+                    target_sequence,
+                    reason,
+                }])
+            }
+            BankAccountCommand::ClearSuspiciousFlag { target_sequence } => {
+                if !self.flagged_sequences.contains(&target_sequence) {
+                    return Err("transaction not flagged".into());
+                }
+                Ok(vec![BankAccountEvent::FlagCleared { target_sequence }])
+            }
+            BankAccountCommand::ReturnDirectDebit {
+                mandate_id,
+                amount,
+                retry_date,
+                currency,
+            } => {
+                self.require_not_closed()?;
+                self.require_currency(&currency)?;
+                if self.direct_debit_retries.contains_key(&mandate_id) {
+                    return Err("direct debit retry already scheduled".into());
+                }
+                let balance = self.credit(amount)?;
+                Ok(vec![BankAccountEvent::DirectDebitReturned {
+                    mandate_id,
+                    amount,
+                    retry_date,
+                    balance,
+                }])
+            }
+            BankAccountCommand::ProcessDirectDebitRetries { as_of } => {
+                self.require_not_closed()?;
+                // A retry that cannot be funded yet stays scheduled for the next run.
+                let mut balance = self.balance;
+                let mut events = Vec::new();
+                for (mandate_id, retry) in &self.direct_debit_retries {
+                    if retry.retry_date > as_of {
+                        continue;
+                    }
+                    balance = match balance.checked_sub(retry.amount) {
+                        Some(after) if after >= self.pending_deposits => after,
+                        _ => continue,
+                    };
+                    events.push(BankAccountEvent::DirectDebitRetried {
+                        mandate_id: mandate_id.clone(),
+                        amount: retry.amount,
+                        balance,
+                    });
+                }
+                Ok(events)
+            }
+            BankAccountCommand::SchedulePayment {
+                payee_id,
+                amount,
+                pay_date,
+            } => {
+                self.require_not_closed()?;
+                if !amount.is_positive() {
+                    return Err("payment amount must be positive".into());
+                }
+                Ok(vec![BankAccountEvent::PaymentScheduled {
+                    payee_id,
+                    amount,
+                    pay_date,
+                }])
+            }
+            BankAccountCommand::ProcessScheduledPayments { as_of } => {
+                self.require_not_closed()?;
+                let mut due: Vec<&ScheduledPayment> = self
+                    .scheduled_payments
+                    .iter()
+                    .filter(|payment| payment.pay_date <= as_of)
+                    .collect();
+                due.sort_by_key(|payment| payment.pay_date);
+                let mut balance = self.balance;
+                let mut events = Vec::new();
+                for payment in due {
+                    balance = match balance.checked_sub(payment.amount) {
+                        Some(after) if after >= self.pending_deposits => after,
+                        _ => {
+                            events.push(BankAccountEvent::PaymentFailed {
+                                payee_id: payment.payee_id.clone(),
+                                amount: payment.amount,
+                                pay_date: payment.pay_date,
+                            });
+                            continue;
+                        }
+                    };
+                    events.push(BankAccountEvent::ScheduledPaymentMade {
+                        payee_id: payment.payee_id.clone(),
+                        amount: payment.amount,
+                        pay_date: payment.pay_date,
+                        balance,
+                    });
+                }
+                Ok(events)
+            }
+            BankAccountCommand::CloseAccount { account_id } => {
+                self.require_not_closed()?;
+                if self.balance != Money::ZERO {
+                    return Err("account balance must be zero to close".into());
+                }
+                Ok(vec![BankAccountEvent::AccountClosed { account_id }])
+            }
+            BankAccountCommand::BackfillCurrency { currency } => {
+                if !self.opened || self.currency.is_some() {
+                    return Ok(vec![]);
+                }
+                Ok(vec![BankAccountEvent::CurrencyBackfilled { currency }])
+            }
+            BankAccountCommand::AddInterest { annual_rate, days } => {
+                self.require_not_closed()?;
+                if !annual_rate.is_finite() {
+                    return Err("interest rate must be a number".into());
+                }
+                if annual_rate < 0.0 {
+                    return Err("interest rate cannot be negative".into());
+                }
+                let cents = self.balance.cents() as f64 * annual_rate * days as f64 / 365.0;
+                let amount = Money::from_cents(cents.round() as i64);
+                // No interest is paid on an empty or overdrawn balance, nor when
+                // it rounds to nothing.
+                if !amount.is_positive() {
+                    return Ok(vec![]);
+                }
+                let balance = self.credit(amount)?;
+                Ok(vec![BankAccountEvent::InterestAccrued { amount, balance }])
+            }
+            BankAccountCommand::VoidCheck { check_number } => {
+                self.require_not_closed()?;
+                let amount = match self.written_checks.get(&check_number) {
+                    Some(amount) => *amount,
+                    None => return Err("check not found".into()),
+                };
+                if self.voided_checks.contains(&check_number) {
+                    return Err("check already voided".into());
+                }
+                let balance = self.credit(amount)?;
+                Ok(vec![BankAccountEvent::CheckVoided {
+                    check_number,
+                    amount,
+                    balance,
+                }])
+            }
+            BankAccountCommand::SetOverdraftPolicy { limit, fee } => {
+                self.require_not_closed()?;
+                if limit.is_negative() {
+                    return Err("overdraft limit cannot be negative".into());
+                }
+                if fee.is_negative() {
+                    return Err("overdraft fee cannot be negative".into());
+                }
+                Ok(vec![BankAccountEvent::OverdraftPolicySet { limit, fee }])
+            }
+            BankAccountCommand::Idempotent {
+                command_id,
+                command,
+            } => {
+                // Each command carries at most one id, an inner one would be
+                // recorded as processed whether or not the outer one was.
+                if matches!(*command, BankAccountCommand::Idempotent { .. }) {
+                    return Err("nested idempotent command".into());
+                }
+                if self.recent_command_ids.contains(&command_id) {
+                    return Ok(vec![]);
+                }
+                let mut events = self.handle(*command, services).await?;
+                events.push(BankAccountEvent::CommandProcessed { command_id });
+                Ok(events)
+            }
+        }
+    }
+
+    fn apply(&mut self, event: Self::Event) {
+        self.event_count += 1;
+        match event {
+            BankAccountEvent::AccountOpened {
+                account_id,
+                currency,
+                holder_name,
+                email,
+            } => {
+                self.opened = true;
+                self.account_id = account_id;
+                self.currency = currency;
+                self.holder_name = holder_name;
+                self.email = email;
+            }
+            BankAccountEvent::CustomerDepositedMoney { amount: _, balance } => {
+                self.balance = balance;
+            }
+            BankAccountEvent::CustomerWithdrewCash {
+                amount,
+                balance,
+                at,
+            }
+            | BankAccountEvent::AtmWithdrawal {
+                amount,
+                balance,
+                at,
+                ..
+            }
+            | BankAccountEvent::PurchaseWithCashback {
+                cashback_amount: amount,
+                balance,
+                at,
+                ..
+            } => {
+                self.record_cash_withdrawal(amount, at);
+                self.balance = balance;
+            }
+            BankAccountEvent::CheckImageDeposited {
+                amount,
+                balance,
+                front_image_ref,
+                ..
+            } => {
+                self.pending_deposits += amount;
+                self.pending_checks.insert(front_image_ref, amount);
+                self.balance = balance;
+            }
+            BankAccountEvent::CheckDepositCleared {
+                front_image_ref,
+                amount,
+            } => {
+                self.pending_deposits -= amount;
+                self.pending_checks.remove(&front_image_ref);
+            }
+            BankAccountEvent::CheckDepositRejected {
+                front_image_ref,
+                amount,
+                balance,
+                ..
+            } => {
+                self.pending_deposits -= amount;
+                self.pending_checks.remove(&front_image_ref);
+                self.balance = balance;
+            }
+            BankAccountEvent::CustomerWroteCheck {
+                check_number,
+                amount,
+                balance,
+                payee: _,
+            } => {
+                self.checks_written += 1;
+                self.written_checks.insert(check_number, amount);
+                self.balance = balance;
+            }
+            BankAccountEvent::IssuedBonus {
+                bonus_id: _,
+                amount: _,
+                balance,
+            } => self.balance = balance,
+            BankAccountEvent::InterestAccrued { balance, .. } => {
+                self.balance = balance;
+            }
+            BankAccountEvent::CheckVoided {
+                check_number,
+                amount: _,
+                balance,
+            } => {
+                self.voided_checks.insert(check_number);
+                self.balance = balance;
+            }
+            BankAccountEvent::OverdraftPolicySet { limit, fee } => {
+                self.overdraft_limit = limit;
+                self.overdraft_fee = fee;
+            }
+            BankAccountEvent::OverdraftFeeCharged { balance, .. } => {
+                self.balance = balance;
+            }
+            BankAccountEvent::CommandProcessed { command_id } => {
+                self.recent_command_ids.push_back(command_id);
+                if self.recent_command_ids.len() > RECENT_COMMAND_IDS {
+                    self.recent_command_ids.pop_front();
+                }
+            }
+            BankAccountEvent::AdjustmentProposed {
+                proposal_id,
+                proposer,
+                delta,
+                reason: _,
+            } => {
+                self.pending_adjustments
+                    .insert(proposal_id, PendingAdjustment { proposer, delta });
+            }
+            BankAccountEvent::AdjustmentApproved {
+                proposal_id,
+                approver: _,
+                delta: _,
+                balance,
+            } => {
+                self.pending_adjustments.remove(&proposal_id);
+                self.balance = balance;
+            }
+            BankAccountEvent::LoanDisbursed {
+                loan_id,
+                amount,
+                balance,
+            } => {
+                self.disbursed_loans.insert(loan_id);
+                self.loan_funds += amount;
+                self.balance = balance;
+            }
+            // Notes are for support agents only and have no effect on the account.
+            BankAccountEvent::AccountNoteAdded { .. } => {}
+            BankAccountEvent::JointHolderAdded { holder_id } => {
+                self.joint_holders.insert(holder_id);
+            }
+            BankAccountEvent::PaperlessEnrolled => {
+                self.paperless = true;
+            }
+            BankAccountEvent::PaperlessDisenrolled => {
+                self.paperless = false;
+            }
+            BankAccountEvent::StatementDelivered { .. } => {}
+            BankAccountEvent::KycStatusRecorded { status, .. } => {
+                self.kyc_status = Some(status);
+            }
+            BankAccountEvent::SpendAlertSet { threshold } => {
+                self.spend_alert_threshold = Some(threshold);
+            }
+            BankAccountEvent::SpendAlertTriggered { .. } => {}
+            BankAccountEvent::LowBalanceThresholdSet { threshold } => {
+                self.low_balance_threshold = Some(threshold);
+            }
+            BankAccountEvent::LowBalanceWarningTriggered { .. } => {}
+            BankAccountEvent::WireSent { balance, .. } => {
+                self.balance = balance;
+            }
+            BankAccountEvent::BookTransferSent {
+                to_account_id,
+                amount,
+                balance,
+            } => {
+                self.sent_transfers.push(SentTransfer {
+                    to_account_id,
+                    amount,
+                });
+                self.balance = balance;
+            }
+            BankAccountEvent::BookTransferReceived { balance, .. } => {
+                self.balance = balance;
+            }
+            BankAccountEvent::BookTransferReversed {
+                to_account_id,
+                amount,
+                balance,
+            } => {
+                let reversed = SentTransfer {
+                    to_account_id,
+                    amount,
+                };
+                if let Some(index) = self
+                    .sent_transfers
+                    .iter()
+                    .position(|sent| *sent == reversed)
+                {
+                    self.sent_transfers.remove(index);
+                }
+                self.balance = balance;
+            }
+            BankAccountEvent::TransactionFlagged {
+                target_sequence, ..
+            } => {
+                self.flagged_sequences.insert(target_sequence);
+            }
+            BankAccountEvent::FlagCleared { target_sequence } => {
+                self.flagged_sequences.remove(&target_sequence);
+            }
+            BankAccountEvent::DirectDebitReturned {
+                mandate_id,
+                amount,
+                retry_date,
+                balance,
+            } => {
+                self.direct_debit_retries
+                    .insert(mandate_id, DirectDebitRetry { amount, retry_date });
+                self.balance = balance;
+            }
+            BankAccountEvent::DirectDebitRetried {
+                mandate_id,
+                balance,
+                ..
+            } => {
+                self.direct_debit_retries.remove(&mandate_id);
+                self.balance = balance;
+            }
+            BankAccountEvent::PaymentScheduled {
+                payee_id,
+                amount,
+                pay_date,
+            } => {
+                self.scheduled_payments.push(ScheduledPayment {
+                    payee_id,
+                    amount,
+                    pay_date,
+                });
+            }
+            BankAccountEvent::ScheduledPaymentMade {
+                payee_id,
+                amount,
+                pay_date,
+                balance,
+            } => {
+                self.remove_scheduled_payment(ScheduledPayment {
+                    payee_id,
+                    amount,
+                    pay_date,
+                });
+                self.balance = balance;
+            }
+            BankAccountEvent::PaymentFailed {
+                payee_id,
+                amount,
+                pay_date,
+            } => {
+                self.remove_scheduled_payment(ScheduledPayment {
+                    payee_id,
+                    amount,
+                    pay_date,
+                });
+            }
+            // Closing the account cancels any payments and direct debit retries
+            // still scheduled, none can be made from a closed account.
+            BankAccountEvent::AccountClosed { .. } => {
+                self.closed = true;
+                self.scheduled_payments.clear();
+                self.direct_debit_retries.clear();
+            }
+            BankAccountEvent::CurrencyBackfilled { currency } => {
+                self.currency = Some(currency);
+            }
+        }
+    }
+}
+
+impl Default for BankAccount {
+    fn default() -> Self {
+        BankAccount {
+            opened: false,
+            account_id: "".to_string(),
+            holder_name: "".to_string(),
+            email: None,
+            balance: Money::ZERO,
+            pending_adjustments: HashMap::new(),
+            disbursed_loans: HashSet::new(),
+            loan_funds: Money::ZERO,
+            paperless: false,
+            joint_holders: HashSet::new(),
+            checks_written: 0,
+            written_checks: HashMap::new(),
+            voided_checks: HashSet::new(),
+            overdraft_limit: Money::ZERO,
+            overdraft_fee: Money::ZERO,
+            pending_deposits: Money::ZERO,
+            pending_checks: HashMap::new(),
+            spend_alert_threshold: None,
+            low_balance_threshold: None,
+            scheduled_payments: Vec::new(),
+            direct_debit_retries: BTreeMap::new(),
+            event_count: 0,
+            flagged_sequences: BTreeSet::new(),
+            kyc_status: None,
+            daily_withdrawn: Money::ZERO,
+            last_withdrawal_date: None,
+            closed: false,
+            currency: None,
+            recent_command_ids: VecDeque::new(),
+            sent_transfers: Vec::new(),
+        }
+    }
+}
+
+impl BankAccount {
+    // Money can only be moved once the account has been opened.
+    fn require_opened(&self) -> Result<(), BankAccountError> {
+        if !self.opened {
+            return Err(BankAccountError::AccountNotOpen);
+        }
+        Ok(())
+    }
+
+    fn require_not_closed(&self) -> Result<(), BankAccountError> {
+        if self.closed {
+            return Err(BankAccountError::AccountClosed);
+        }
+        Ok(())
+    }
+
+    // Accounts opened before their currency was recorded hold US dollars.
+    fn require_currency(&self, currency: &str) -> Result<(), BankAccountError> {
+        if currency != self.currency() {
+            return Err(BankAccountError::CurrencyMismatch);
+        }
+        Ok(())
+    }
+
+    fn currency(&self) -> &str {
+        self.currency.as_deref().unwrap_or(ACCOUNT_CURRENCY)
+    }
+
+    // Every withdrawal of cash counts towards the same daily limit, whether from
+    // a teller, an ATM or as cash back.
+    fn require_daily_withdrawal_limit(
+        &self,
+        amount: Money,
+        at: DateTime<Utc>,
+        services: &BankAccountServices,
+    ) -> Result<(), BankAccountError> {
+        let withdrawn_today = if self.last_withdrawal_date == Some(at.date_naive()) {
+            self.daily_withdrawn
+        } else {
+            Money::ZERO
+        };
+        let withdrawn_today = withdrawn_today
+            .checked_add(amount)
+            .ok_or(BankAccountError::AmountOutOfRange)?;
+        if withdrawn_today > services.daily_withdrawal_limit {
+            return Err("daily withdrawal limit exceeded".into());
+        }
+        Ok(())
+    }
+
+    fn record_cash_withdrawal(&mut self, amount: Money, at: DateTime<Utc>) {
+        let date = at.date_naive();
+        if self.last_withdrawal_date == Some(date) {
+            self.daily_withdrawn += amount;
+        } else {
+            self.daily_withdrawn = amount;
+            self.last_withdrawal_date = Some(date);
+        }
+    }
+
+    fn require_kyc(&self, amount: Money) -> Result<(), BankAccountError> {
+        if amount > KYC_THRESHOLD && self.kyc_status != Some(KycStatus::Verified) {
+            return Err("kyc verification required".into());
+        }
+        Ok(())
+    }
+
+    // The amount of the check deposit still held under `front_image_ref`.
+    fn pending_check(&self, front_image_ref: &str) -> Result<Money, BankAccountError> {
+        match self.pending_checks.get(front_image_ref) {
+            Some(amount) => Ok(*amount),
+            None => Err("check deposit not found".into()),
+        }
+    }
+
+    // The balance after crediting `amount`, which cannot take it beyond what
+    // `Money` holds.
+    fn credit(&self, amount: Money) -> Result<Money, BankAccountError> {
+        self.balance
+            .checked_add(amount)
+            .ok_or(BankAccountError::AmountOutOfRange)
+    }
+
+    // The balance after debiting `amount`. The amount must be positive and the
+    // account cannot be overdrawn, nor can funds still being held be debited.
+    fn debit(&self, amount: Money) -> Result<Money, BankAccountError> {
+        if !amount.is_positive() {
+            return Err(BankAccountError::InvalidAmount);
+        }
+        let balance = self
+            .balance
+            .checked_sub(amount)
+            .ok_or(BankAccountError::AmountOutOfRange)?;
+        if balance < self.pending_deposits {
+            return Err(BankAccountError::InsufficientFunds {
+                requested: amount,
+                available: self.balance - self.pending_deposits,
+            });
+        }
+        Ok(balance)
+    }
+
+    // As `debit`, but the account may be overdrawn by up to its overdraft limit,
+    // including the overdraft fee that is then charged. Returns the balance after
+    // the debit along with the fee, if one is charged.
+    fn debit_with_overdraft(
+        &self,
+        amount: Money,
+    ) -> Result<(Money, Option<BankAccountEvent>), BankAccountError> {
+        if !amount.is_positive() {
+            return Err(BankAccountError::InvalidAmount);
+        }
+        let balance = self
+            .balance
+            .checked_sub(amount)
+            .ok_or(BankAccountError::AmountOutOfRange)?;
+        if balance >= self.pending_deposits {
+            return Ok((balance, None));
+        }
+        let balance_after_fee = balance
+            .checked_sub(self.overdraft_fee)
+            .ok_or(BankAccountError::AmountOutOfRange)?;
+        if balance_after_fee < self.pending_deposits - self.overdraft_limit {
+            let available = self.balance - self.pending_deposits;
+            return Err(BankAccountError::InsufficientFunds {
+                requested: amount,
+                available: available.max(available + self.overdraft_limit - self.overdraft_fee),
+            });
+        }
+        if !self.overdraft_fee.is_positive() {
+            return Ok((balance, None));
+        }
+        let fee = BankAccountEvent::OverdraftFeeCharged {
+            fee: self.overdraft_fee,
+            balance: balance_after_fee,
+        };
+        Ok((balance, Some(fee)))
+    }
+
+    fn remove_scheduled_payment(&mut self, processed: ScheduledPayment) {
+        if let Some(index) = self
+            .scheduled_payments
+            .iter()
+            .position(|payment| *payment == processed)
+        {
+            self.scheduled_payments.remove(index);
+        }
+    }
+
+    // The alert to record with a debit of this amount, if it exceeds the spend alert threshold.
+    fn spend_alert(&self, amount: Money) -> Option<BankAccountEvent> {
+        self.spend_alert_threshold
+            .filter(|threshold| amount > *threshold)
+            .map(|threshold| BankAccountEvent::SpendAlertTriggered { amount, threshold })
+    }
+
+    // The warning to record after these events, if they take the balance from at or
+    // above the low balance threshold to below it.
+    fn low_balance_warning(&self, events: &[BankAccountEvent]) -> Option<BankAccountEvent> {
+        let threshold = self.low_balance_threshold?;
+        let balance = events.iter().rev().find_map(BankAccountEvent::balance)?;
+        (self.balance >= threshold && balance < threshold)
+            .then_some(BankAccountEvent::LowBalanceWarningTriggered { balance, threshold })
+    }
+
+    // Large withdrawals from a joint account must be authorized by one of its joint holders.
+    fn authorize_joint_withdrawal(
+        &self,
+        amount: Money,
+        authorized_by: Option<String>,
+    ) -> Result<(), BankAccountError> {
+        if self.joint_holders.is_empty() || amount <= JOINT_AUTHORIZATION_THRESHOLD {
+            return Ok(());
+        }
+        match authorized_by {
+            Some(holder_id) if self.joint_holders.contains(&holder_id) => Ok(()),
+            _ => Err("joint holder authorization required".into()),
+        }
+    }
+
+    // A one-line description of the account for CLI tooling, e.g.,
+    // `account abc123: balance 123.45 USD, 2 checks, open`.
+    pub fn summary(&self) -> String {
+        let status = if !self.opened {
+            "not opened"
+        } else if self.closed {
+            "closed"
+        } else {
+            "open"
+        };
+        let checks = if self.checks_written == 1 {
+            "check"
+        } else {
+            "checks"
+        };
+        format!(
+            "account {}: balance {} {}, {} {}, {}",
+            self.account_id,
+            self.balance,
+            self.currency(),
+            self.checks_written,
+            checks,
+            status
+        )
+    }
+}
+
+// The aggregate tests are the most important part of a CQRS system.
+// The simplicity and flexibility of these tests are a good part of what
+// makes an event sourced system so friendly to changing business requirements.
+#[cfg(test)]
+mod aggregate_tests {
+    use async_trait::async_trait;
+    use chrono::{DateTime, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    use cqrs_es::test::TestFramework;
+    use cqrs_es::Aggregate;
+
+    use crate::domain::aggregate::{BankAccount, RECENT_COMMAND_IDS};
+    use crate::domain::check_number::CheckNumber;
+    use crate::domain::commands::BankAccountCommand;
+    use crate::domain::errors::BankAccountError;
+    use crate::domain::events::{BankAccountEvent, KycStatus, StatementChannel};
+    use crate::domain::money::Money;
+    use crate::services::{AtmError, BankAccountApi, BankAccountServices, CheckingError};
+
+    // A test framework that will apply our events and command
+    // and verify that the logic works as expected.
+    type AccountTestFramework = TestFramework<BankAccount>;
+
+    // Most commands are only accepted by an account that has been opened.
+    fn account_opened() -> BankAccountEvent {
+        BankAccountEvent::AccountOpened {
+            account_id: "ACCT-1".to_string(),
+            currency: Some("USD".to_string()),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
+        }
+    }
+
+    // A fixed clock for commands that record when they occurred.
+    fn test_clock() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2023, 3, 1, 9, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn test_open_account() {
+        let expected = BankAccountEvent::AccountOpened {
+            account_id: "ACCT-1".to_string(),
+            currency: Some("USD".to_string()),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
+        };
+        let command = BankAccountCommand::OpenAccount {
+            account_id: "ACCT-1".to_string(),
+            currency: "USD".to_string(),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given_no_previous_events()
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_open_account_retry_is_idempotent() {
+        let previous = BankAccountEvent::AccountOpened {
+            account_id: "ACCT-1".to_string(),
+            currency: Some("USD".to_string()),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
+        };
+        let command = BankAccountCommand::OpenAccount {
+            account_id: "ACCT-1".to_string(),
+            currency: "USD".to_string(),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![previous])
+            .when(command)
+            .then_expect_events(vec![]);
+    }
+
+    #[test]
+    fn test_open_account_conflicting_id() {
+        let previous = BankAccountEvent::AccountOpened {
+            account_id: "ACCT-1".to_string(),
+            currency: Some("USD".to_string()),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
+        };
+        let command = BankAccountCommand::OpenAccount {
+            account_id: "ACCT-2".to_string(),
+            currency: "USD".to_string(),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![previous])
+            .when(command)
+            .then_expect_error_message("account already open");
+    }
+
+    #[test]
+    fn test_deposit_money() {
+        let expected = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(20_000),
+            balance: Money::from_cents(20_000),
+        };
+        let command = BankAccountCommand::DepositMoney {
+            amount: Money::from_cents(20_000),
+            currency: "USD".to_string(),
+        };
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        // Obtain a new test framework
+        AccountTestFramework::with(services)
+            // In a test case with no previous events
+            .given(vec![account_opened()])
+            // Wnen we fire this command
+            .when(command)
+            // then we expect these results
+            .then_expect_events(vec![expected]);
+    }
+
+    // A deposit tagged with a client-supplied command id.
+    fn idempotent_deposit(command_id: Uuid) -> BankAccountCommand {
+        BankAccountCommand::Idempotent {
+            command_id,
+            command: Box::new(BankAccountCommand::DepositMoney {
+                amount: Money::from_cents(20_000),
+                currency: "USD".to_string(),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_idempotent_deposit() {
+        let command_id = Uuid::from_u128(1);
+        let expected = vec![
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(20_000),
+                balance: Money::from_cents(20_000),
+            },
+            BankAccountEvent::CommandProcessed { command_id },
+        ];
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened()])
+            .when(idempotent_deposit(command_id))
+            .then_expect_events(expected);
+    }
+
+    #[test]
+    fn test_idempotent_deposit_repeated_command_id() {
+        let command_id = Uuid::from_u128(1);
+        let previous = vec![
+            account_opened(),
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(20_000),
+                balance: Money::from_cents(20_000),
+            },
+            BankAccountEvent::CommandProcessed { command_id },
+        ];
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(idempotent_deposit(command_id))
+            .then_expect_events(vec![]);
+    }
+
+    #[test]
+    fn test_nested_idempotent_command() {
+        let command = BankAccountCommand::Idempotent {
+            command_id: Uuid::from_u128(1),
+            command: Box::new(idempotent_deposit(Uuid::from_u128(2))),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened()])
+            .when(command)
+            .then_expect_error_message("nested idempotent command");
+    }
+
+    #[test]
+    fn test_idempotent_deposit_new_command_id() {
+        let previous = vec![
+            account_opened(),
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(20_000),
+                balance: Money::from_cents(20_000),
+            },
+            BankAccountEvent::CommandProcessed {
+                command_id: Uuid::from_u128(1),
+            },
+        ];
+        let expected = vec![
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(20_000),
+                balance: Money::from_cents(40_000),
+            },
+            BankAccountEvent::CommandProcessed {
+                command_id: Uuid::from_u128(2),
+            },
+        ];
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(idempotent_deposit(Uuid::from_u128(2)))
+            .then_expect_events(expected);
+    }
+
+    #[test]
+    fn test_only_recent_command_ids_are_remembered() {
+        let mut account = BankAccount::default();
+        for id in 0..=RECENT_COMMAND_IDS as u128 {
+            account.apply(BankAccountEvent::CommandProcessed {
+                command_id: Uuid::from_u128(id),
+            });
+        }
+        assert_eq!(account.recent_command_ids.len(), RECENT_COMMAND_IDS);
+        assert!(!account.recent_command_ids.contains(&Uuid::from_u128(0)));
+        assert!(account.recent_command_ids.contains(&Uuid::from_u128(1)));
+    }
+
+    #[test]
+    fn test_deposit_money_with_balance() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(20_000),
+            balance: Money::from_cents(20_000),
+        };
+        let expected = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(20_000),
+            balance: Money::from_cents(40_000),
+        };
+        let command = BankAccountCommand::DepositMoney {
+            amount: Money::from_cents(20_000),
+            currency: "USD".to_string(),
+        };
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+
+        AccountTestFramework::with(services)
+            // Given this previously applied event
+            .given(vec![account_opened(), previous])
+            // When we fire this command
+            .when(command)
+            // Then we expect this resultant event
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_withdraw_money() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(20_000),
+            balance: Money::from_cents(20_000),
+        };
+        let expected = BankAccountEvent::CustomerWithdrewCash {
+            amount: Money::from_cents(10_000),
+            balance: Money::from_cents(10_000),
+            at: test_clock(),
+        };
+        let services = MockBankAccountServices::default();
+        services.set_atm_withdrawal_response(Ok(()));
+        let command = BankAccountCommand::WithdrawMoney {
+            amount: Money::from_cents(10_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            authorized_by: None,
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(services)).with_clock(test_clock);
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_withdraw_money_client_error() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(20_000),
+            balance: Money::from_cents(20_000),
+        };
+        let services = MockBankAccountServices::default();
+        services.set_atm_withdrawal_response(Err(AtmError));
+        let command = BankAccountCommand::WithdrawMoney {
+            amount: Money::from_cents(10_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            authorized_by: None,
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(services));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_error_message("atm rule violation");
+    }
+
+    #[test]
+    fn test_withdraw_money_funds_not_available() {
+        let command = BankAccountCommand::WithdrawMoney {
+            amount: Money::from_cents(20_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            authorized_by: None,
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened()])
+            .when(command)
+            // Here we expect an error rather than any events
+            .then_expect_error_message("funds not available")
+    }
+
+    #[test]
+    fn test_withdraw_at_atm() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(20_000),
+            balance: Money::from_cents(20_000),
+        };
+        let expected = BankAccountEvent::AtmWithdrawal {
+            amount: Money::from_cents(6_000),
+            balance: Money::from_cents(14_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            location: "Portland, OR".to_string(),
+            at: test_clock(),
+        };
+        let services = MockBankAccountServices::default();
+        services.set_atm_withdrawal_response(Ok(()));
+        let command = BankAccountCommand::WithdrawAtAtm {
+            amount: Money::from_cents(6_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            location: "Portland, OR".to_string(),
+            authorized_by: None,
+        };
+
+        let services = BankAccountServices::new(Box::new(services)).with_clock(test_clock);
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_withdraw_at_atm_daily_withdrawal_limit_exceeded() {
+        let previous = vec![
+            account_opened(),
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(200_000),
+                balance: Money::from_cents(200_000),
+            },
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(60_000),
+                balance: Money::from_cents(140_000),
+                at: test_clock() - Duration::hours(1),
+            },
+        ];
+        let services = MockBankAccountServices::default();
+        services.set_atm_withdrawal_response(Ok(()));
+        let command = BankAccountCommand::WithdrawAtAtm {
+            amount: Money::from_cents(60_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            location: "Portland, OR".to_string(),
+            authorized_by: None,
+        };
+
+        let services = BankAccountServices::new(Box::new(services)).with_clock(test_clock);
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_error_message("daily withdrawal limit exceeded");
+    }
+
+    #[test]
+    fn test_atm_withdrawal_counts_towards_daily_withdrawal_limit() {
+        let previous = vec![
+            account_opened(),
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(200_000),
+                balance: Money::from_cents(200_000),
+            },
+            BankAccountEvent::AtmWithdrawal {
+                amount: Money::from_cents(60_000),
+                balance: Money::from_cents(140_000),
+                atm_id: "ATM34f1ba3c".to_string(),
+                location: "Portland, OR".to_string(),
+                at: test_clock() - Duration::hours(1),
+            },
+        ];
+        let services = MockBankAccountServices::default();
+        services.set_atm_withdrawal_response(Ok(()));
+        let command = BankAccountCommand::WithdrawMoney {
+            amount: Money::from_cents(60_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            authorized_by: None,
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(services)).with_clock(test_clock);
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_error_message("daily withdrawal limit exceeded");
+    }
+
+    #[test]
+    fn test_withdraw_at_atm_funds_not_available() {
+        let command = BankAccountCommand::WithdrawAtAtm {
+            amount: Money::from_cents(6_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            location: "Portland, OR".to_string(),
+            authorized_by: None,
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened()])
+            .when(command)
+            .then_expect_error_message("funds not available")
+    }
+
+    #[test]
+    fn test_withdraw_money_insufficient_funds_error() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(10_000),
+            balance: Money::from_cents(10_000),
+        };
+        let command = BankAccountCommand::WithdrawMoney {
+            amount: Money::from_cents(25_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            authorized_by: None,
+            currency: "USD".to_string(),
+        };
+        let expected = BankAccountError::InsufficientFunds {
+            requested: Money::from_cents(25_000),
+            available: Money::from_cents(10_000),
+        };
+        assert_eq!(expected.to_string(), "funds not available");
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_error(expected)
+    }
+
+    #[test]
+    fn test_withdraw_with_cashback() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(10_000),
+            balance: Money::from_cents(10_000),
+        };
+        let expected = BankAccountEvent::PurchaseWithCashback {
+            purchase_amount: Money::from_cents(4_250),
+            cashback_amount: Money::from_cents(4_000),
+            merchant: "Corner Grocery".to_string(),
+            balance: Money::from_cents(1_750),
+            at: test_clock(),
+        };
+        let command = BankAccountCommand::WithdrawWithCashback {
+            purchase_amount: Money::from_cents(4_250),
+            cashback_amount: Money::from_cents(4_000),
+            merchant: "Corner Grocery".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()))
+            .with_clock(test_clock);
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_withdraw_with_cashback_funds_not_available() {
+        // The purchase alone is covered but not the purchase and cash back together.
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(6_000),
+            balance: Money::from_cents(6_000),
+        };
+        let command = BankAccountCommand::WithdrawWithCashback {
+            purchase_amount: Money::from_cents(4_250),
+            cashback_amount: Money::from_cents(4_000),
+            merchant: "Corner Grocery".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_error_message("funds not available");
+    }
+
+    #[test]
+    fn test_withdraw_with_zero_cashback() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(6_000),
+            balance: Money::from_cents(6_000),
+        };
+        let command = BankAccountCommand::WithdrawWithCashback {
+            purchase_amount: Money::from_cents(4_250),
+            cashback_amount: Money::ZERO,
+            merchant: "Corner Grocery".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_error_message("cashback amount must be positive");
+    }
+
+    #[test]
+    fn test_wrote_check() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(20_000),
+            balance: Money::from_cents(20_000),
+        };
+        let expected = BankAccountEvent::CustomerWroteCheck {
+            check_number: CheckNumber::new("1170").unwrap(),
+            amount: Money::from_cents(10_000),
+            balance: Money::from_cents(10_000),
+            payee: None,
+        };
+        let services = MockBankAccountServices::default();
+        services.set_validate_check_response(Ok(()));
+        let services = BankAccountServices::new(Box::new(services));
+        let command = BankAccountCommand::WriteCheck {
+            check_number: CheckNumber::new("1170").unwrap(),
+            amount: Money::from_cents(10_000),
+            payee: None,
+            currency: "USD".to_string(),
+        };
+
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_wrote_check_bad_check() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(20_000),
+            balance: Money::from_cents(20_000),
+        };
+        let services = MockBankAccountServices::default();
+        services.set_validate_check_response(Err(CheckingError));
+        let services = BankAccountServices::new(Box::new(services));
+        let command = BankAccountCommand::WriteCheck {
+            check_number: CheckNumber::new("1170").unwrap(),
+            amount: Money::from_cents(10_000),
+            payee: None,
+            currency: "USD".to_string(),
+        };
+
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_error_message("check invalid");
+    }
+
+    #[test]
+    fn test_wrote_check_funds_not_available() {
+        let command = BankAccountCommand::WriteCheck {
+            check_number: CheckNumber::new("1170").unwrap(),
+            amount: Money::from_cents(10_000),
+            payee: None,
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened()])
+            .when(command)
+            .then_expect_error_message("funds not available")
+    }
+
+    #[test]
+    fn test_propose_reconciliation_adjustment() {
+        let expected = BankAccountEvent::AdjustmentProposed {
+            proposal_id: "ADJ-1".to_string(),
+            proposer: "alice".to_string(),
+            delta: Money::from_cents(-1_250),
+            reason: "duplicate posting".to_string(),
+        };
+        let command = BankAccountCommand::ProposeReconciliationAdjustment {
+            proposal_id: "ADJ-1".to_string(),
+            proposer: "alice".to_string(),
+            delta: Money::from_cents(-1_250),
+            reason: "duplicate posting".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given_no_previous_events()
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_approve_reconciliation_adjustment() {
+        let deposited = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(20_000),
+            balance: Money::from_cents(20_000),
+        };
+        let proposed = BankAccountEvent::AdjustmentProposed {
+            proposal_id: "ADJ-1".to_string(),
+            proposer: "alice".to_string(),
+            delta: Money::from_cents(-1_250),
+            reason: "duplicate posting".to_string(),
+        };
+        let expected = BankAccountEvent::AdjustmentApproved {
+            proposal_id: "ADJ-1".to_string(),
+            approver: "bob".to_string(),
+            delta: Money::from_cents(-1_250),
+            balance: Money::from_cents(18_750),
+        };
+        let command = BankAccountCommand::ApproveReconciliationAdjustment {
+            proposal_id: "ADJ-1".to_string(),
+            approver: "bob".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![deposited, proposed])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_approve_reconciliation_adjustment_self_approval() {
+        let proposed = BankAccountEvent::AdjustmentProposed {
+            proposal_id: "ADJ-1".to_string(),
+            proposer: "alice".to_string(),
+            delta: Money::from_cents(-1_250),
+            reason: "duplicate posting".to_string(),
+        };
+        let command = BankAccountCommand::ApproveReconciliationAdjustment {
+            proposal_id: "ADJ-1".to_string(),
+            approver: "alice".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![proposed])
+            .when(command)
+            .then_expect_error_message("adjustment cannot be approved by its proposer");
+    }
+
+    #[test]
+    fn test_disburse_loan() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(20_000),
+            balance: Money::from_cents(20_000),
+        };
+        let expected = BankAccountEvent::LoanDisbursed {
+            loan_id: "LN-3310".to_string(),
+            amount: Money::from_cents(500_000),
+            balance: Money::from_cents(520_000),
+        };
+        let command = BankAccountCommand::DisburseLoan {
+            loan_id: "LN-3310".to_string(),
+            amount: Money::from_cents(500_000),
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_disburse_loan_duplicate() {
+        let previous = BankAccountEvent::LoanDisbursed {
+            loan_id: "LN-3310".to_string(),
+            amount: Money::from_cents(500_000),
+            balance: Money::from_cents(500_000),
+        };
+        let command = BankAccountCommand::DisburseLoan {
+            loan_id: "LN-3310".to_string(),
+            amount: Money::from_cents(500_000),
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_error_message("loan already disbursed");
+    }
+
+    #[test]
+    fn test_add_account_note() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(20_000),
+            balance: Money::from_cents(20_000),
+        };
+        let expected = BankAccountEvent::AccountNoteAdded {
+            note: "customer called about a lost card".to_string(),
+            timestamp: test_clock(),
+        };
+        let command = BankAccountCommand::AddAccountNote {
+            note: "customer called about a lost card".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()))
+            .with_clock(test_clock);
+        AccountTestFramework::with(services)
+            .given(vec![previous])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_add_account_note_empty() {
+        let command = BankAccountCommand::AddAccountNote {
+            note: "  ".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given_no_previous_events()
+            .when(command)
+            .then_expect_error_message("note cannot be empty");
+    }
+
+    #[test]
+    fn test_enroll_paperless() {
+        let command = BankAccountCommand::EnrollPaperless;
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given_no_previous_events()
+            .when(command)
+            .then_expect_events(vec![BankAccountEvent::PaperlessEnrolled]);
+    }
+
+    #[test]
+    fn test_statement_delivery_defaults_to_email_when_paperless() {
+        let expected = BankAccountEvent::StatementDelivered {
+            statement_id: "2023-03".to_string(),
+            channel: StatementChannel::Email,
+        };
+        let command = BankAccountCommand::RecordStatementDelivery {
+            statement_id: "2023-03".to_string(),
+            channel: None,
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![BankAccountEvent::PaperlessEnrolled])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_statement_delivery_defaults_to_mail_after_disenrolling() {
+        let expected = BankAccountEvent::StatementDelivered {
+            statement_id: "2023-03".to_string(),
+            channel: StatementChannel::Mail,
+        };
+        let command = BankAccountCommand::RecordStatementDelivery {
+            statement_id: "2023-03".to_string(),
+            channel: None,
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![
+                BankAccountEvent::PaperlessEnrolled,
+                BankAccountEvent::PaperlessDisenrolled,
+            ])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_disenroll_paperless_when_not_enrolled() {
+        let command = BankAccountCommand::DisenrollPaperless;
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given_no_previous_events()
+            .when(command)
+            .then_expect_error_message("not enrolled in paperless statements");
+    }
+
+    #[test]
+    fn test_add_joint_holder() {
+        let previous = BankAccountEvent::JointHolderAdded {
+            holder_id: "CUST-1".to_string(),
+        };
+        let expected = BankAccountEvent::JointHolderAdded {
+            holder_id: "CUST-2".to_string(),
+        };
+        let command = BankAccountCommand::AddJointHolder {
+            holder_id: "CUST-2".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![previous])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_add_joint_holder_duplicate() {
+        let previous = BankAccountEvent::JointHolderAdded {
+            holder_id: "CUST-1".to_string(),
+        };
+        let command = BankAccountCommand::AddJointHolder {
+            holder_id: "CUST-1".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![previous])
+            .when(command)
+            .then_expect_error_message("joint holder already added");
+    }
+
+    #[test]
+    fn test_joint_withdrawal_requires_authorization() {
+        let previous = vec![
+            account_opened(),
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(500_000),
+                balance: Money::from_cents(500_000),
+            },
+            BankAccountEvent::JointHolderAdded {
+                holder_id: "CUST-1".to_string(),
+            },
+        ];
+        let command = BankAccountCommand::WithdrawMoney {
+            amount: Money::from_cents(200_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            authorized_by: Some("CUST-9".to_string()),
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_error_message("joint holder authorization required");
+    }
+
+    #[test]
+    fn test_joint_withdrawal_authorized() {
+        let previous = vec![
+            account_opened(),
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(500_000),
+                balance: Money::from_cents(500_000),
+            },
+            BankAccountEvent::JointHolderAdded {
+                holder_id: "CUST-1".to_string(),
+            },
+        ];
+        let expected = BankAccountEvent::CustomerWithdrewCash {
+            amount: Money::from_cents(200_000),
+            balance: Money::from_cents(300_000),
+            at: test_clock(),
+        };
+        let services = MockBankAccountServices::default();
+        services.set_atm_withdrawal_response(Ok(()));
+        let command = BankAccountCommand::WithdrawMoney {
+            amount: Money::from_cents(200_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            authorized_by: Some("CUST-1".to_string()),
+            currency: "USD".to_string(),
+        };
+
+        // Above the default daily withdrawal limit.
+        let services = BankAccountServices::new(Box::new(services))
+            .with_clock(test_clock)
+            .with_daily_withdrawal_limit(Money::from_cents(500_000));
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut account = BankAccount::default();
+        assert_eq!(
+            account.summary(),
+            "account : balance 0.00 USD, 0 checks, not opened"
+        );
+
+        account.apply(BankAccountEvent::AccountOpened {
+            account_id: "abc123".to_string(),
+            currency: Some("USD".to_string()),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
+        });
+        account.apply(BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(50_000),
+            balance: Money::from_cents(50_000),
+        });
+        account.apply(BankAccountEvent::CustomerWroteCheck {
+            check_number: CheckNumber::new("1170").unwrap(),
+            amount: Money::from_cents(30_000),
+            balance: Money::from_cents(20_000),
+            payee: None,
+        });
+        account.apply(BankAccountEvent::CustomerWroteCheck {
+            check_number: CheckNumber::new("1171").unwrap(),
+            amount: Money::from_cents(7_655),
+            balance: Money::from_cents(12_345),
+            payee: None,
+        });
+        assert_eq!(
+            account.summary(),
+            "account abc123: balance 123.45 USD, 2 checks, open"
+        );
+    }
+
+    #[test]
+    fn test_deposit_check_image() {
+        let expected = BankAccountEvent::CheckImageDeposited {
+            amount: Money::from_cents(25_000),
+            balance: Money::from_cents(25_000),
+            front_image_ref: "images/1170-front.png".to_string(),
+            back_image_ref: "images/1170-back.png".to_string(),
+        };
+        let command = BankAccountCommand::DepositCheckImage {
+            amount: Money::from_cents(25_000),
+            front_image_ref: "images/1170-front.png".to_string(),
+            back_image_ref: "images/1170-back.png".to_string(),
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened()])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_deposit_check_image_missing_back_image() {
+        let command = BankAccountCommand::DepositCheckImage {
+            amount: Money::from_cents(25_000),
+            front_image_ref: "images/1170-front.png".to_string(),
+            back_image_ref: "".to_string(),
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened()])
+            .when(command)
+            .then_expect_error_message("check images required");
+    }
+
+    #[test]
+    fn test_pending_check_image_deposit_cannot_be_withdrawn() {
+        let previous = BankAccountEvent::CheckImageDeposited {
+            amount: Money::from_cents(25_000),
+            balance: Money::from_cents(25_000),
+            front_image_ref: "images/1170-front.png".to_string(),
+            back_image_ref: "images/1170-back.png".to_string(),
+        };
+        let command = BankAccountCommand::WithdrawMoney {
+            amount: Money::from_cents(10_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            authorized_by: None,
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_error_message("funds not available");
+    }
+
+    fn check_image_deposited() -> BankAccountEvent {
+        BankAccountEvent::CheckImageDeposited {
+            amount: Money::from_cents(25_000),
+            balance: Money::from_cents(25_000),
+            front_image_ref: "images/1170-front.png".to_string(),
+            back_image_ref: "images/1170-back.png".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_cleared_check_deposit_can_be_withdrawn() {
+        let cleared = BankAccountEvent::CheckDepositCleared {
+            front_image_ref: "images/1170-front.png".to_string(),
+            amount: Money::from_cents(25_000),
+        };
+        let expected = BankAccountEvent::CustomerWithdrewCash {
+            amount: Money::from_cents(10_000),
+            balance: Money::from_cents(15_000),
+            at: test_clock(),
+        };
+        let command = BankAccountCommand::WithdrawMoney {
+            amount: Money::from_cents(10_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            authorized_by: None,
+            currency: "USD".to_string(),
+        };
+
+        let services = MockBankAccountServices::default();
+        services.set_atm_withdrawal_response(Ok(()));
+        let services = BankAccountServices::new(Box::new(services)).with_clock(test_clock);
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), check_image_deposited(), cleared])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_clear_check_deposit() {
+        let expected = BankAccountEvent::CheckDepositCleared {
+            front_image_ref: "images/1170-front.png".to_string(),
+            amount: Money::from_cents(25_000),
+        };
+        let command = BankAccountCommand::ClearCheckDeposit {
+            front_image_ref: "images/1170-front.png".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), check_image_deposited()])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_reject_check_deposit() {
+        let expected = BankAccountEvent::CheckDepositRejected {
+            front_image_ref: "images/1170-front.png".to_string(),
+            amount: Money::from_cents(25_000),
+            balance: Money::ZERO,
+            reason: "signature missing".to_string(),
+        };
+        let command = BankAccountCommand::RejectCheckDeposit {
+            front_image_ref: "images/1170-front.png".to_string(),
+            reason: "signature missing".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), check_image_deposited()])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_reject_cleared_check_deposit() {
+        let cleared = BankAccountEvent::CheckDepositCleared {
+            front_image_ref: "images/1170-front.png".to_string(),
+            amount: Money::from_cents(25_000),
+        };
+        let command = BankAccountCommand::RejectCheckDeposit {
+            front_image_ref: "images/1170-front.png".to_string(),
+            reason: "signature missing".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), check_image_deposited(), cleared])
+            .when(command)
+            .then_expect_error_message("check deposit not found");
+    }
+
+    #[test]
+    fn test_record_kyc() {
+        let expected = BankAccountEvent::KycStatusRecorded {
+            status: KycStatus::Verified,
+            verified_at: test_clock(),
+        };
+        let command = BankAccountCommand::RecordKyc {
+            status: KycStatus::Verified,
+            verified_at: test_clock(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given_no_previous_events()
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_large_deposit_kyc_verified() {
+        let previous = BankAccountEvent::KycStatusRecorded {
+            status: KycStatus::Verified,
+            verified_at: test_clock(),
+        };
+        let expected = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(2_500_000),
+            balance: Money::from_cents(2_500_000),
+        };
+        let command = BankAccountCommand::DepositMoney {
+            amount: Money::from_cents(2_500_000),
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_large_deposit_kyc_not_verified() {
+        let previous = BankAccountEvent::KycStatusRecorded {
+            status: KycStatus::Pending,
+            verified_at: test_clock(),
+        };
+        let command = BankAccountCommand::DepositMoney {
+            amount: Money::from_cents(2_500_000),
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_error_message("kyc verification required");
+    }
+
+    #[test]
+    fn test_set_spend_alert() {
+        let expected = BankAccountEvent::SpendAlertSet {
+            threshold: Money::from_cents(50_000),
+        };
+        let command = BankAccountCommand::SetSpendAlert {
+            threshold: Money::from_cents(50_000),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given_no_previous_events()
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_set_spend_alert_negative_threshold() {
+        let command = BankAccountCommand::SetSpendAlert {
+            threshold: Money::from_cents(-100),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given_no_previous_events()
+            .when(command)
+            .then_expect_error_message("spend alert threshold cannot be negative");
+    }
+
+    #[test]
+    fn test_withdrawal_above_spend_alert_threshold() {
+        let previous = vec![
+            account_opened(),
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(100_000),
+                balance: Money::from_cents(100_000),
+            },
+            BankAccountEvent::SpendAlertSet {
+                threshold: Money::from_cents(50_000),
+            },
+        ];
+        let expected = vec![
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(60_000),
+                balance: Money::from_cents(40_000),
+                at: test_clock(),
+            },
+            BankAccountEvent::SpendAlertTriggered {
+                amount: Money::from_cents(60_000),
+                threshold: Money::from_cents(50_000),
+            },
+        ];
+        let services = MockBankAccountServices::default();
+        services.set_atm_withdrawal_response(Ok(()));
+        let command = BankAccountCommand::WithdrawMoney {
+            amount: Money::from_cents(60_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            authorized_by: None,
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(services)).with_clock(test_clock);
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_events(expected);
+    }
+
+    #[test]
+    fn test_set_low_balance_threshold() {
+        let expected = BankAccountEvent::LowBalanceThresholdSet {
+            threshold: Money::from_cents(50_000),
+        };
+        let command = BankAccountCommand::SetLowBalanceThreshold {
+            threshold: Money::from_cents(50_000),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened()])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    // An account holding 1000.00 that warns when its balance drops below 500.00.
+    fn low_balance_account() -> Vec<BankAccountEvent> {
+        vec![
+            account_opened(),
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(100_000),
+                balance: Money::from_cents(100_000),
+            },
+            BankAccountEvent::LowBalanceThresholdSet {
+                threshold: Money::from_cents(50_000),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_withdrawal_crossing_low_balance_threshold() {
+        let expected = vec![
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(60_000),
+                balance: Money::from_cents(40_000),
+                at: test_clock(),
+            },
+            BankAccountEvent::LowBalanceWarningTriggered {
+                balance: Money::from_cents(40_000),
+                threshold: Money::from_cents(50_000),
+            },
+        ];
+        let services = MockBankAccountServices::default();
+        services.set_atm_withdrawal_response(Ok(()));
+        let command = BankAccountCommand::WithdrawMoney {
+            amount: Money::from_cents(60_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            authorized_by: None,
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(services)).with_clock(test_clock);
+        AccountTestFramework::with(services)
+            .given(low_balance_account())
+            .when(command)
+            .then_expect_events(expected);
+    }
+
+    #[test]
+    fn test_low_balance_warning_not_repeated_while_below_threshold() {
+        let mut previous = low_balance_account();
+        previous.extend(vec![
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(60_000),
+                balance: Money::from_cents(40_000),
+                at: test_clock(),
+            },
+            BankAccountEvent::LowBalanceWarningTriggered {
+                balance: Money::from_cents(40_000),
+                threshold: Money::from_cents(50_000),
+            },
+        ]);
+        let expected = BankAccountEvent::CustomerWroteCheck {
+            check_number: CheckNumber::new("1170").unwrap(),
+            amount: Money::from_cents(10_000),
+            balance: Money::from_cents(30_000),
+            payee: None,
+        };
+        let services = MockBankAccountServices::default();
+        services.set_validate_check_response(Ok(()));
+        let command = BankAccountCommand::WriteCheck {
+            check_number: CheckNumber::new("1170").unwrap(),
+            amount: Money::from_cents(10_000),
+            payee: None,
+            currency: "USD".to_string(),
+        };
+
+        AccountTestFramework::with(BankAccountServices::new(Box::new(services)))
+            .given(previous)
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_deposit_above_low_balance_threshold_rearms_warning() {
+        let mut previous = low_balance_account();
+        previous.extend(vec![
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(60_000),
+                balance: Money::from_cents(40_000),
+                at: test_clock(),
+            },
+            BankAccountEvent::LowBalanceWarningTriggered {
+                balance: Money::from_cents(40_000),
+                threshold: Money::from_cents(50_000),
+            },
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(20_000),
+                balance: Money::from_cents(60_000),
+            },
+        ]);
+        let expected = vec![
+            BankAccountEvent::CustomerWroteCheck {
+                check_number: CheckNumber::new("1170").unwrap(),
+                amount: Money::from_cents(20_000),
+                balance: Money::from_cents(40_000),
+                payee: None,
+            },
+            BankAccountEvent::LowBalanceWarningTriggered {
+                balance: Money::from_cents(40_000),
+                threshold: Money::from_cents(50_000),
+            },
+        ];
+        let services = MockBankAccountServices::default();
+        services.set_validate_check_response(Ok(()));
+        let command = BankAccountCommand::WriteCheck {
+            check_number: CheckNumber::new("1170").unwrap(),
+            amount: Money::from_cents(20_000),
+            payee: None,
+            currency: "USD".to_string(),
+        };
+
+        AccountTestFramework::with(BankAccountServices::new(Box::new(services)))
+            .given(previous)
+            .when(command)
+            .then_expect_events(expected);
+    }
+
+    #[test]
+    fn test_send_wire() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(100_000),
+            balance: Money::from_cents(100_000),
+        };
+        let expected = BankAccountEvent::WireSent {
+            amount: Money::from_cents(70_000),
+            fee: Money::from_cents(2_500),
+            balance: Money::from_cents(27_500),
+            beneficiary: "ACME Corp".to_string(),
+        };
+        let command = BankAccountCommand::SendWire {
+            amount: Money::from_cents(70_000),
+            beneficiary: "ACME Corp".to_string(),
+            fee: Money::from_cents(2_500),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()))
+            .with_clock(test_clock);
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_send_wire_after_cutoff() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(100_000),
+            balance: Money::from_cents(100_000),
+        };
+        let command = BankAccountCommand::SendWire {
+            amount: Money::from_cents(70_000),
+            beneficiary: "ACME Corp".to_string(),
+            fee: Money::from_cents(2_500),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()))
+            .with_clock(test_clock)
+            .with_wire_cutoff(NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_error_message("wire cutoff has passed for today");
+    }
+
+    #[test]
+    fn test_book_transfer() {
+        let previous = vec![
+            BankAccountEvent::AccountOpened {
+                account_id: "ACCT-1".to_string(),
+                currency: Some("USD".to_string()),
+                holder_name: "Jane Doe".to_string(),
+                email: None,
+            },
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(50_000),
+                balance: Money::from_cents(50_000),
+            },
+        ];
+        let expected = BankAccountEvent::BookTransferSent {
+            to_account_id: "ACCT-2".to_string(),
+            amount: Money::from_cents(20_000),
+            balance: Money::from_cents(30_000),
+        };
+        let command = BankAccountCommand::BookTransfer {
+            to_account_id: "ACCT-2".to_string(),
+            amount: Money::from_cents(20_000),
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_reverse_book_transfer() {
+        let previous = vec![
+            account_opened(),
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(50_000),
+                balance: Money::from_cents(50_000),
+            },
+            BankAccountEvent::BookTransferSent {
+                to_account_id: "ACCT-2".to_string(),
+                amount: Money::from_cents(20_000),
+                balance: Money::from_cents(30_000),
+            },
+        ];
+        let expected = BankAccountEvent::BookTransferReversed {
+            to_account_id: "ACCT-2".to_string(),
+            amount: Money::from_cents(20_000),
+            balance: Money::from_cents(50_000),
+        };
+        let command = BankAccountCommand::ReverseBookTransfer {
+            to_account_id: "ACCT-2".to_string(),
+            amount: Money::from_cents(20_000),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_reverse_book_transfer_not_sent() {
+        let previous = vec![
+            account_opened(),
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(50_000),
+                balance: Money::from_cents(50_000),
+            },
+            BankAccountEvent::BookTransferSent {
+                to_account_id: "ACCT-2".to_string(),
+                amount: Money::from_cents(20_000),
+                balance: Money::from_cents(30_000),
+            },
+        ];
+        let command = BankAccountCommand::ReverseBookTransfer {
+            to_account_id: "ACCT-2".to_string(),
+            amount: Money::from_cents(90_000),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_error_message("book transfer not found");
+    }
+
+    #[test]
+    fn test_reverse_book_transfer_already_reversed() {
+        let previous = vec![
+            account_opened(),
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(50_000),
+                balance: Money::from_cents(50_000),
+            },
+            BankAccountEvent::BookTransferSent {
+                to_account_id: "ACCT-2".to_string(),
+                amount: Money::from_cents(20_000),
+                balance: Money::from_cents(30_000),
+            },
+            BankAccountEvent::BookTransferReversed {
+                to_account_id: "ACCT-2".to_string(),
+                amount: Money::from_cents(20_000),
+                balance: Money::from_cents(50_000),
+            },
+        ];
+        let command = BankAccountCommand::ReverseBookTransfer {
+            to_account_id: "ACCT-2".to_string(),
+            amount: Money::from_cents(20_000),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_error_message("book transfer not found");
+    }
+
+    #[test]
+    fn test_receive_book_transfer_currency_mismatch() {
+        let command = BankAccountCommand::ReceiveBookTransfer {
+            from_account_id: "ACCT-2".to_string(),
+            amount: Money::from_cents(20_000),
+            currency: "EUR".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened()])
+            .when(command)
+            .then_expect_error(BankAccountError::CurrencyMismatch);
+    }
+
+    #[test]
+    fn test_disburse_loan_currency_mismatch() {
+        let command = BankAccountCommand::DisburseLoan {
+            loan_id: "LOAN-1".to_string(),
+            amount: Money::from_cents(100_000),
+            currency: "EUR".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened()])
+            .when(command)
+            .then_expect_error(BankAccountError::CurrencyMismatch);
+    }
+
+    #[test]
+    fn test_receive_book_transfer_account_not_open() {
+        let command = BankAccountCommand::ReceiveBookTransfer {
+            from_account_id: "ACCT-1".to_string(),
+            amount: Money::from_cents(20_000),
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given_no_previous_events()
+            .when(command)
+            .then_expect_error_message("account not open");
+    }
+
+    fn flagged_deposit() -> Vec<BankAccountEvent> {
+        vec![
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(950_000),
+                balance: Money::from_cents(950_000),
+            },
+            BankAccountEvent::TransactionFlagged {
+                target_sequence: 1,
+                reason: "structuring".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_flag_suspicious() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(950_000),
+            balance: Money::from_cents(950_000),
+        };
+        let command = BankAccountCommand::FlagSuspicious {
+            target_sequence: 1,
+            reason: "structuring".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![previous])
+            .when(command)
+            .then_expect_events(flagged_deposit()[1..].to_vec());
+    }
+
+    #[test]
+    fn test_flag_suspicious_nonexistent_sequence() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(950_000),
+            balance: Money::from_cents(950_000),
+        };
+        let command = BankAccountCommand::FlagSuspicious {
+            target_sequence: 2,
+            reason: "structuring".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![previous])
+            .when(command)
+            .then_expect_error_message("transaction not found");
+    }
+
+    #[test]
+    fn test_clear_suspicious_flag() {
+        let command = BankAccountCommand::ClearSuspiciousFlag { target_sequence: 1 };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(flagged_deposit())
+            .when(command)
+            .then_expect_events(vec![BankAccountEvent::FlagCleared { target_sequence: 1 }]);
+    }
+
+    #[test]
+    fn test_clear_suspicious_flag_not_flagged() {
+        let mut previous = flagged_deposit();
+        previous.push(BankAccountEvent::FlagCleared { target_sequence: 1 });
+        let command = BankAccountCommand::ClearSuspiciousFlag { target_sequence: 1 };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_error_message("transaction not flagged");
+    }
+
+    #[test]
+    fn test_return_direct_debit() {
+        let expected = BankAccountEvent::DirectDebitReturned {
+            mandate_id: "MANDATE-GYM".to_string(),
+            amount: Money::from_cents(4_500),
+            retry_date: NaiveDate::from_ymd_opt(2023, 3, 8).unwrap(),
+            balance: Money::from_cents(4_500),
+        };
+        let command = BankAccountCommand::ReturnDirectDebit {
+            mandate_id: "MANDATE-GYM".to_string(),
+            amount: Money::from_cents(4_500),
+            retry_date: NaiveDate::from_ymd_opt(2023, 3, 8).unwrap(),
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given_no_previous_events()
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_process_direct_debit_retries() {
+        let previous = vec![
+            BankAccountEvent::DirectDebitReturned {
+                mandate_id: "MANDATE-GYM".to_string(),
+                amount: Money::from_cents(4_500),
+                retry_date: NaiveDate::from_ymd_opt(2023, 3, 8).unwrap(),
+                balance: Money::from_cents(4_500),
+            },
+            BankAccountEvent::DirectDebitReturned {
+                mandate_id: "MANDATE-PHONE".to_string(),
+                amount: Money::from_cents(3_000),
+                retry_date: NaiveDate::from_ymd_opt(2023, 3, 20).unwrap(),
+                balance: Money::from_cents(7_500),
+            },
+        ];
+        let expected = BankAccountEvent::DirectDebitRetried {
+            mandate_id: "MANDATE-GYM".to_string(),
+            amount: Money::from_cents(4_500),
+            balance: Money::from_cents(3_000),
+        };
+        let command = BankAccountCommand::ProcessDirectDebitRetries {
+            as_of: NaiveDate::from_ymd_opt(2023, 3, 8).unwrap(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    fn scheduled(payee_id: &str, amount: Money, day: u32) -> BankAccountEvent {
+        BankAccountEvent::PaymentScheduled {
+            payee_id: payee_id.to_string(),
+            amount,
+            pay_date: NaiveDate::from_ymd_opt(2023, 3, day).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_schedule_payment() {
+        let command = BankAccountCommand::SchedulePayment {
+            payee_id: "PAYEE-ELECTRIC".to_string(),
+            amount: Money::from_cents(8_000),
+            pay_date: NaiveDate::from_ymd_opt(2023, 3, 15).unwrap(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given_no_previous_events()
+            .when(command)
+            .then_expect_events(vec![scheduled(
+                "PAYEE-ELECTRIC",
+                Money::from_cents(8_000),
+                15,
+            )]);
+    }
+
+    #[test]
+    fn test_process_scheduled_payments() {
+        let previous = vec![
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(20_000),
+                balance: Money::from_cents(20_000),
+            },
+            scheduled("PAYEE-ELECTRIC", Money::from_cents(8_000), 15),
+            scheduled("PAYEE-WATER", Money::from_cents(3_000), 10),
+            scheduled("PAYEE-RENT", Money::from_cents(90_000), 31),
+        ];
+        let expected = vec![
+            BankAccountEvent::ScheduledPaymentMade {
+                payee_id: "PAYEE-WATER".to_string(),
+                amount: Money::from_cents(3_000),
+                pay_date: NaiveDate::from_ymd_opt(2023, 3, 10).unwrap(),
+                balance: Money::from_cents(17_000),
+            },
+            BankAccountEvent::ScheduledPaymentMade {
+                payee_id: "PAYEE-ELECTRIC".to_string(),
+                amount: Money::from_cents(8_000),
+                pay_date: NaiveDate::from_ymd_opt(2023, 3, 15).unwrap(),
+                balance: Money::from_cents(9_000),
+            },
+        ];
+        let command = BankAccountCommand::ProcessScheduledPayments {
+            as_of: NaiveDate::from_ymd_opt(2023, 3, 15).unwrap(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_events(expected);
+    }
+
+    #[test]
+    fn test_process_scheduled_payments_insufficient_funds() {
+        let previous = vec![
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(5_000),
+                balance: Money::from_cents(5_000),
+            },
+            scheduled("PAYEE-ELECTRIC", Money::from_cents(8_000), 15),
+        ];
+        let expected = BankAccountEvent::PaymentFailed {
+            payee_id: "PAYEE-ELECTRIC".to_string(),
+            amount: Money::from_cents(8_000),
+            pay_date: NaiveDate::from_ymd_opt(2023, 3, 15).unwrap(),
+        };
+        let command = BankAccountCommand::ProcessScheduledPayments {
+            as_of: NaiveDate::from_ymd_opt(2023, 3, 15).unwrap(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_debit() {
+        let account = funded_account();
+        assert_eq!(
+            account.debit(Money::from_cents(12_000)).unwrap(),
+            Money::from_cents(38_000)
+        );
+        assert_eq!(
+            account.debit(Money::from_cents(50_000)).unwrap(),
+            Money::ZERO
+        );
+        assert_eq!(
+            account
+                .debit(Money::from_cents(50_001))
+                .unwrap_err()
+                .to_string(),
+            "funds not available"
+        );
+        assert_eq!(
+            account
+                .debit(Money::from_cents(-500))
+                .unwrap_err()
+                .to_string(),
+            "amount must be positive"
+        );
+    }
+
+    #[test]
+    fn test_close_account() {
+        let opened = BankAccountEvent::AccountOpened {
+            account_id: "ACCT-1".to_string(),
+            currency: Some("USD".to_string()),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
+        };
+        let expected = BankAccountEvent::AccountClosed {
+            account_id: "ACCT-1".to_string(),
+        };
+        let command = BankAccountCommand::CloseAccount {
+            account_id: "ACCT-1".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![opened])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_close_account_with_balance() {
+        let opened = BankAccountEvent::AccountOpened {
+            account_id: "ACCT-1".to_string(),
+            currency: Some("USD".to_string()),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
+        };
+        let deposited = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(20_000),
+            balance: Money::from_cents(20_000),
+        };
+        let command = BankAccountCommand::CloseAccount {
+            account_id: "ACCT-1".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![opened, deposited])
+            .when(command)
+            .then_expect_error_message("account balance must be zero to close");
+    }
+
+    #[test]
+    fn test_deposit_money_closed_account() {
+        let opened = BankAccountEvent::AccountOpened {
+            account_id: "ACCT-1".to_string(),
+            currency: Some("USD".to_string()),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
+        };
+        let closed = BankAccountEvent::AccountClosed {
+            account_id: "ACCT-1".to_string(),
+        };
+        let command = BankAccountCommand::DepositMoney {
+            amount: Money::from_cents(20_000),
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![opened, closed])
+            .when(command)
+            .then_expect_error_message("account is closed");
+    }
+
+    // The events of an account that has been opened and then closed.
+    fn closed_account() -> Vec<BankAccountEvent> {
+        vec![
+            account_opened(),
+            BankAccountEvent::AccountClosed {
+                account_id: "ACCT-1".to_string(),
+            },
+        ]
+    }
+
+    fn assert_rejected_when_closed(command: BankAccountCommand) {
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(closed_account())
+            .when(command)
+            .then_expect_error(BankAccountError::AccountClosed);
+    }
+
+    fn assert_rejected_before_open(command: BankAccountCommand) {
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given_no_previous_events()
+            .when(command)
+            .then_expect_error(BankAccountError::AccountNotOpen);
+    }
+
+    #[test]
+    fn test_money_moving_commands_before_open() {
+        assert_rejected_before_open(BankAccountCommand::WithdrawAtAtm {
+            amount: Money::from_cents(6_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            location: "Portland, OR".to_string(),
+            authorized_by: None,
+        });
+        assert_rejected_before_open(BankAccountCommand::DepositCheckImage {
+            amount: Money::from_cents(25_000),
+            front_image_ref: "images/1170-front.png".to_string(),
+            back_image_ref: "images/1170-back.png".to_string(),
+            currency: "USD".to_string(),
+        });
+        assert_rejected_before_open(BankAccountCommand::SendWire {
+            amount: Money::from_cents(10_000),
+            beneficiary: "ACME Corp".to_string(),
+            fee: Money::from_cents(2_500),
+        });
+        assert_rejected_before_open(BankAccountCommand::WithdrawWithCashback {
+            purchase_amount: Money::from_cents(4_250),
+            cashback_amount: Money::from_cents(4_000),
+            merchant: "Corner Grocery".to_string(),
+        });
+        assert_rejected_before_open(BankAccountCommand::BookTransfer {
+            to_account_id: "ACCT-2".to_string(),
+            amount: Money::from_cents(20_000),
+            currency: "USD".to_string(),
+        });
+        assert_rejected_before_open(BankAccountCommand::IssueBonus {
+            bonus_id: "BONUS-1".to_string(),
+            amount: Money::from_cents(5_000),
+            currency: "USD".to_string(),
+        });
+        assert_rejected_before_open(BankAccountCommand::DisburseLoan {
+            loan_id: "LOAN-1".to_string(),
+            amount: Money::from_cents(100_000),
+            currency: "USD".to_string(),
+        });
+    }
+
+    #[test]
+    fn test_issue_bonus_closed_account() {
+        assert_rejected_when_closed(BankAccountCommand::IssueBonus {
+            bonus_id: "BONUS-1".to_string(),
+            amount: Money::from_cents(5_000),
+            currency: "USD".to_string(),
+        });
+    }
+
+    #[test]
+    fn test_approve_reconciliation_adjustment_closed_account() {
+        assert_rejected_when_closed(BankAccountCommand::ApproveReconciliationAdjustment {
+            proposal_id: "ADJ-1".to_string(),
+            approver: "auditor".to_string(),
+        });
+    }
+
+    #[test]
+    fn test_disburse_loan_closed_account() {
+        assert_rejected_when_closed(BankAccountCommand::DisburseLoan {
+            loan_id: "LOAN-1".to_string(),
+            amount: Money::from_cents(100_000),
+            currency: "USD".to_string(),
+        });
+    }
+
+    #[test]
+    fn test_return_direct_debit_closed_account() {
+        assert_rejected_when_closed(BankAccountCommand::ReturnDirectDebit {
+            mandate_id: "MANDATE-1".to_string(),
+            amount: Money::from_cents(5_000),
+            retry_date: NaiveDate::from_ymd_opt(2023, 3, 8).unwrap(),
+            currency: "USD".to_string(),
+        });
+    }
+
+    #[test]
+    fn test_process_direct_debit_retries_closed_account() {
+        assert_rejected_when_closed(BankAccountCommand::ProcessDirectDebitRetries {
+            as_of: NaiveDate::from_ymd_opt(2023, 3, 8).unwrap(),
+        });
+    }
+
+    #[test]
+    fn test_schedule_payment_closed_account() {
+        assert_rejected_when_closed(BankAccountCommand::SchedulePayment {
+            payee_id: "PAYEE-1".to_string(),
+            amount: Money::from_cents(5_000),
+            pay_date: NaiveDate::from_ymd_opt(2023, 3, 8).unwrap(),
+        });
+    }
+
+    #[test]
+    fn test_process_scheduled_payments_closed_account() {
+        assert_rejected_when_closed(BankAccountCommand::ProcessScheduledPayments {
+            as_of: NaiveDate::from_ymd_opt(2023, 3, 8).unwrap(),
+        });
+    }
+
+    #[test]
+    fn test_add_interest_closed_account() {
+        assert_rejected_when_closed(BankAccountCommand::AddInterest {
+            annual_rate: 0.05,
+            days: 30,
+        });
+    }
+
+    #[test]
+    fn test_set_overdraft_policy_closed_account() {
+        assert_rejected_when_closed(BankAccountCommand::SetOverdraftPolicy {
+            limit: Money::from_cents(10_000),
+            fee: Money::from_cents(2_500),
+        });
+    }
+
+    #[test]
+    fn test_close_account_cancels_scheduled_payments_and_retries() {
+        let mut account = BankAccount::default();
+        let events = [
+            account_opened(),
+            BankAccountEvent::PaymentScheduled {
+                payee_id: "PAYEE-1".to_string(),
+                amount: Money::from_cents(5_000),
+                pay_date: NaiveDate::from_ymd_opt(2023, 3, 8).unwrap(),
+            },
+            BankAccountEvent::DirectDebitReturned {
+                mandate_id: "MANDATE-1".to_string(),
+                amount: Money::from_cents(5_000),
+                retry_date: NaiveDate::from_ymd_opt(2023, 3, 8).unwrap(),
+                balance: Money::from_cents(5_000),
+            },
+            BankAccountEvent::CustomerWroteCheck {
+                check_number: CheckNumber::new("1170").unwrap(),
+                amount: Money::from_cents(5_000),
+                balance: Money::ZERO,
+                payee: None,
+            },
+            BankAccountEvent::AccountClosed {
+                account_id: "ACCT-1".to_string(),
+            },
+        ];
+        for event in events {
+            account.apply(event);
+        }
+
+        assert!(account.scheduled_payments.is_empty());
+        assert!(account.direct_debit_retries.is_empty());
+    }
+
+    #[test]
+    fn test_add_interest() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(100_000),
+            balance: Money::from_cents(100_000),
+        };
+        // 1000.00 at 5% for 30 days is 4.1096, rounded to the nearest cent.
+        let expected = BankAccountEvent::InterestAccrued {
+            amount: Money::from_cents(411),
+            balance: Money::from_cents(100_411),
+        };
+        let command = BankAccountCommand::AddInterest {
+            annual_rate: 0.05,
+            days: 30,
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![previous])
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_add_interest_zero_days() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(100_000),
+            balance: Money::from_cents(100_000),
+        };
+        let command = BankAccountCommand::AddInterest {
+            annual_rate: 0.05,
+            days: 0,
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![previous])
+            .when(command)
+            .then_expect_events(vec![]);
+    }
+
+    #[test]
+    fn test_add_interest_negative_rate() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(100_000),
+            balance: Money::from_cents(100_000),
+        };
+        let command = BankAccountCommand::AddInterest {
+            annual_rate: -0.01,
+            days: 30,
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![previous])
+            .when(command)
+            .then_expect_error_message("interest rate cannot be negative");
+    }
+
+    #[test]
+    fn test_add_interest_nan_rate() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(100_000),
+            balance: Money::from_cents(100_000),
+        };
+        let command = BankAccountCommand::AddInterest {
+            annual_rate: f64::NAN,
+            days: 30,
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![previous])
+            .when(command)
+            .then_expect_error_message("interest rate must be a number");
+    }
+
+    #[test]
+    fn test_add_interest_overdrawn_balance() {
+        let previous = vec![
+            account_opened(),
+            BankAccountEvent::OverdraftPolicySet {
+                limit: Money::from_cents(10_000),
+                fee: Money::ZERO,
+            },
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(5_000),
+                balance: Money::from_cents(-5_000),
+                at: test_clock(),
+            },
+        ];
+        let command = BankAccountCommand::AddInterest {
+            annual_rate: 0.05,
+            days: 30,
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_events(vec![]);
+    }
+
+    #[test]
+    fn test_deposit_negative_amount() {
+        let command = BankAccountCommand::DepositMoney {
+            amount: Money::from_cents(-5_000),
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened()])
+            .when(command)
+            .then_expect_error_message("amount must be positive");
+    }
+
+    #[test]
+    fn test_withdraw_zero_amount() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(20_000),
+            balance: Money::from_cents(20_000),
+        };
+        let command = BankAccountCommand::WithdrawMoney {
+            amount: Money::ZERO,
+            atm_id: "ATM34f1ba3c".to_string(),
+            authorized_by: None,
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_error_message("amount must be positive");
+    }
+
+    #[test]
+    fn test_write_check_negative_amount() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(20_000),
+            balance: Money::from_cents(20_000),
+        };
+        let command = BankAccountCommand::WriteCheck {
+            check_number: CheckNumber::new("1170").unwrap(),
+            amount: Money::from_cents(-5_000),
+            payee: None,
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_error_message("amount must be positive");
+    }
+
+    #[test]
+    fn test_deposit_money_overflow() {
+        let previous = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(i64::MAX - 100),
+            balance: Money::from_cents(i64::MAX - 100),
+        };
+        let command = BankAccountCommand::DepositMoney {
+            amount: Money::from_cents(101),
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), previous])
+            .when(command)
+            .then_expect_error_message("amount out of range");
+    }
+
+    #[test]
+    fn test_withdraw_money_overflow() {
+        let previous = vec![
+            account_opened(),
+            BankAccountEvent::OverdraftPolicySet {
+                limit: Money::from_cents(10_000),
+                fee: Money::ZERO,
+            },
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(5_000),
+                balance: Money::from_cents(-5_000),
+                at: test_clock() - Duration::days(1),
+            },
+        ];
+        let command = BankAccountCommand::WithdrawMoney {
+            amount: Money::from_cents(i64::MAX),
+            atm_id: "ATM34f1ba3c".to_string(),
+            authorized_by: None,
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_error(BankAccountError::AmountOutOfRange);
+    }
+
+    #[test]
+    fn test_daily_withdrawal_limit_exceeded() {
+        let previous = vec![
+            account_opened(),
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(200_000),
+                balance: Money::from_cents(200_000),
+            },
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(60_000),
+                balance: Money::from_cents(140_000),
+                at: test_clock() - Duration::hours(1),
+            },
+        ];
+        let services = MockBankAccountServices::default();
+        services.set_atm_withdrawal_response(Ok(()));
+        let command = BankAccountCommand::WithdrawMoney {
+            amount: Money::from_cents(60_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            authorized_by: None,
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(services)).with_clock(test_clock);
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_error_message("daily withdrawal limit exceeded");
+    }
+
+    #[test]
+    fn test_daily_withdrawal_limit_resets_each_day() {
+        let previous = vec![
+            account_opened(),
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(200_000),
+                balance: Money::from_cents(200_000),
+            },
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(60_000),
+                balance: Money::from_cents(140_000),
+                at: test_clock() - Duration::days(1),
+            },
+        ];
+        let expected = BankAccountEvent::CustomerWithdrewCash {
+            amount: Money::from_cents(60_000),
+            balance: Money::from_cents(80_000),
+            at: test_clock(),
+        };
+        let services = MockBankAccountServices::default();
+        services.set_atm_withdrawal_response(Ok(()));
+        let command = BankAccountCommand::WithdrawMoney {
+            amount: Money::from_cents(60_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            authorized_by: None,
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(services)).with_clock(test_clock);
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_events(vec![expected]);
+    }
+
+    #[test]
+    fn test_deposit_before_open() {
+        let command = BankAccountCommand::DepositMoney {
+            amount: Money::from_cents(20_000),
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given_no_previous_events()
+            .when(command)
+            .then_expect_error_message("account not open");
     }
 
-    fn apply(&mut self, event: Self::Event) {
-        match event {
-            BankAccountEvent::AccountOpened { account_id } => {
-                self.account_id = account_id;
-            }
-            BankAccountEvent::CustomerDepositedMoney { amount: _, balance } => {
-                self.balance = balance;
-            }
-            BankAccountEvent::CustomerWithdrewCash { amount: _, balance } => {
-                self.balance = balance;
-            }
+    #[test]
+    fn test_write_check_number_already_used() {
+        let previous = vec![
+            account_opened(),
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(50_000),
+                balance: Money::from_cents(50_000),
+            },
             BankAccountEvent::CustomerWroteCheck {
-                check_number: _,
-                amount: _,
-                balance,
-            } => {
-                self.balance = balance;
-            }
-            BankAccountEvent::IssuedBonus{bonus_id: _, amount: _, balance} => {
-                self.balance = balance
+                check_number: CheckNumber::new("1170").unwrap(),
+                amount: Money::from_cents(10_000),
+                balance: Money::from_cents(40_000),
+                payee: None,
+            },
+        ];
+        let command = BankAccountCommand::WriteCheck {
+            check_number: CheckNumber::new("1170").unwrap(),
+            amount: Money::from_cents(5_000),
+            payee: None,
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(previous)
+            .when(command)
+            .then_expect_error_message("check number already used");
+    }
+
+    #[test]
+    fn test_write_check_invalid_number_from_request() {
+        let command: BankAccountCommand = serde_json::from_value(serde_json::json!({
+            "WriteCheck": {
+                "check_number": "A-17",
+                "amount": "50.00",
+                "payee": null,
+                "currency": "USD"
             }
-        }
+        }))
+        .unwrap();
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened()])
+            .when(command)
+            .then_expect_error_message("check number must be numeric");
     }
-}
 
-impl Default for BankAccount {
-    fn default() -> Self {
-        BankAccount {
-            account_id: "".to_string(),
-            balance: 0_f64,
-        }
+    #[test]
+    fn test_write_check_before_open() {
+        let command = BankAccountCommand::WriteCheck {
+            check_number: CheckNumber::new("1170").unwrap(),
+            amount: Money::from_cents(5_000),
+            payee: None,
+            currency: "USD".to_string(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given_no_previous_events()
+            .when(command)
+            .then_expect_error_message("account not open");
     }
-}
 
-// The aggregate tests are the most important part of a CQRS system.
-// The simplicity and flexibility of these tests are a good part of what
-// makes an event sourced system so friendly to changing business requirements.
-#[cfg(test)]
-mod aggregate_tests {
-    use async_trait::async_trait;
-    use std::sync::Mutex;
+    #[test]
+    fn test_open_account_twice_is_rejected() {
+        let command = BankAccountCommand::OpenAccount {
+            account_id: "ACCT-2".to_string(),
+            currency: "USD".to_string(),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
+        };
 
-    use cqrs_es::test::TestFramework;
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![
+                account_opened(),
+                BankAccountEvent::CustomerDepositedMoney {
+                    amount: Money::from_cents(20_000),
+                    balance: Money::from_cents(20_000),
+                },
+            ])
+            .when(command)
+            .then_expect_error_message("account already open");
+    }
 
-    use crate::domain::aggregate::BankAccount;
-    use crate::domain::commands::BankAccountCommand;
-    use crate::domain::events::BankAccountEvent;
-    use crate::services::{AtmError, BankAccountApi, BankAccountServices, CheckingError};
+    #[test]
+    fn test_deposit_currency_mismatch() {
+        let command = BankAccountCommand::DepositMoney {
+            amount: Money::from_cents(20_000),
+            currency: "EUR".to_string(),
+        };
 
-    // A test framework that will apply our events and command
-    // and verify that the logic works as expected.
-    type AccountTestFramework = TestFramework<BankAccount>;
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened()])
+            .when(command)
+            .then_expect_error_message("currency mismatch");
+    }
 
     #[test]
-    fn test_deposit_money() {
+    fn test_deposit_in_account_currency() {
+        let opened = BankAccountEvent::AccountOpened {
+            account_id: "ACCT-1".to_string(),
+            currency: Some("EUR".to_string()),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
+        };
         let expected = BankAccountEvent::CustomerDepositedMoney {
-            amount: 200.0,
-            balance: 200.0,
+            amount: Money::from_cents(20_000),
+            balance: Money::from_cents(20_000),
+        };
+        let command = BankAccountCommand::DepositMoney {
+            amount: Money::from_cents(20_000),
+            currency: "EUR".to_string(),
         };
-        let command = BankAccountCommand::DepositMoney { amount: 200.0 };
+
         let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
-        // Obtain a new test framework
         AccountTestFramework::with(services)
-            // In a test case with no previous events
-            .given_no_previous_events()
-            // Wnen we fire this command
+            .given(vec![opened])
             .when(command)
-            // then we expect these results
             .then_expect_events(vec![expected]);
     }
 
-
     #[test]
-    fn test_deposit_money_with_balance() {
-        let previous = BankAccountEvent::CustomerDepositedMoney {
-            amount: 200.0,
-            balance: 200.0,
+    fn test_legacy_account_currency_is_usd() {
+        let opened = BankAccountEvent::AccountOpened {
+            account_id: "ACCT-1".to_string(),
+            currency: None,
+            holder_name: String::new(),
+            email: None,
         };
-        let expected = BankAccountEvent::CustomerDepositedMoney {
-            amount: 200.0,
-            balance: 400.0,
+        let command = BankAccountCommand::WriteCheck {
+            check_number: CheckNumber::new("1170").unwrap(),
+            amount: Money::from_cents(5_000),
+            payee: None,
+            currency: "EUR".to_string(),
         };
-        let command = BankAccountCommand::DepositMoney { amount: 200.0 };
-        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
 
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
         AccountTestFramework::with(services)
-            // Given this previously applied event
-            .given(vec![previous])
-            // When we fire this command
+            .given(vec![opened])
             .when(command)
-            // Then we expect this resultant event
-            .then_expect_events(vec![expected]);
+            .then_expect_error_message("currency mismatch");
     }
 
     #[test]
-    fn test_withdraw_money() {
-        let previous = BankAccountEvent::CustomerDepositedMoney {
-            amount: 200.0,
-            balance: 200.0,
+    fn test_open_account_invalid_currency() {
+        let command = BankAccountCommand::OpenAccount {
+            account_id: "ACCT-1".to_string(),
+            currency: "dollars".to_string(),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
         };
-        let expected = BankAccountEvent::CustomerWithdrewCash {
-            amount: 100.0,
-            balance: 100.0,
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given_no_previous_events()
+            .when(command)
+            .then_expect_error_message("invalid currency");
+    }
+
+    #[test]
+    fn test_open_account_with_holder_details() {
+        let expected = BankAccountEvent::AccountOpened {
+            account_id: "ACCT-1".to_string(),
+            currency: Some("USD".to_string()),
+            holder_name: "Jane Doe".to_string(),
+            email: Some("jane.doe@example.com".to_string()),
         };
-        let services = MockBankAccountServices::default();
-        services.set_atm_withdrawal_response(Ok(()));
-        let command = BankAccountCommand::WithdrawMoney {
-            amount: 100.0,
-            atm_id: "ATM34f1ba3c".to_string(),
+        let command = BankAccountCommand::OpenAccount {
+            account_id: "ACCT-1".to_string(),
+            currency: "USD".to_string(),
+            holder_name: "Jane Doe".to_string(),
+            email: Some("jane.doe@example.com".to_string()),
         };
 
-        AccountTestFramework::with(BankAccountServices::new(Box::new(services)))
-            .given(vec![previous])
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given_no_previous_events()
             .when(command)
             .then_expect_events(vec![expected]);
     }
 
     #[test]
-    fn test_withdraw_money_client_error() {
-        let previous = BankAccountEvent::CustomerDepositedMoney {
-            amount: 200.0,
-            balance: 200.0,
-        };
-        let services = MockBankAccountServices::default();
-        services.set_atm_withdrawal_response(Err(AtmError));
-        let command = BankAccountCommand::WithdrawMoney {
-            amount: 100.0,
-            atm_id: "ATM34f1ba3c".to_string(),
+    fn test_open_account_holder_name_required() {
+        let command = BankAccountCommand::OpenAccount {
+            account_id: "ACCT-1".to_string(),
+            currency: "USD".to_string(),
+            holder_name: " ".to_string(),
+            email: None,
         };
 
-        let services = BankAccountServices::new(Box::new(services));
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
         AccountTestFramework::with(services)
-            .given(vec![previous])
+            .given_no_previous_events()
             .when(command)
-            .then_expect_error_message("atm rule violation");
+            .then_expect_error_message("holder name required");
     }
 
     #[test]
-    fn test_withdraw_money_funds_not_available() {
-        let command = BankAccountCommand::WithdrawMoney {
-            amount: 200.0,
-            atm_id: "ATM34f1ba3c".to_string(),
+    fn test_open_account_invalid_email() {
+        let command = BankAccountCommand::OpenAccount {
+            account_id: "ACCT-1".to_string(),
+            currency: "USD".to_string(),
+            holder_name: "Jane Doe".to_string(),
+            email: Some("jane.doe.example.com".to_string()),
         };
 
         let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
         AccountTestFramework::with(services)
             .given_no_previous_events()
             .when(command)
-            // Here we expect an error rather than any events
-            .then_expect_error_message("funds not available")
+            .then_expect_error_message("invalid email");
+    }
+
+    fn written_check() -> BankAccountEvent {
+        BankAccountEvent::CustomerWroteCheck {
+            check_number: CheckNumber::new("1170").unwrap(),
+            amount: Money::from_cents(25_000),
+            balance: Money::from_cents(75_000),
+            payee: None,
+        }
     }
 
     #[test]
-    fn test_wrote_check() {
-        let previous = BankAccountEvent::CustomerDepositedMoney {
-            amount: 200.0,
-            balance: 200.0,
+    fn test_void_check() {
+        let deposited = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(100_000),
+            balance: Money::from_cents(100_000),
         };
-        let expected = BankAccountEvent::CustomerWroteCheck {
-            check_number: "1170".to_string(),
-            amount: 100.0,
-            balance: 100.0,
+        let expected = BankAccountEvent::CheckVoided {
+            check_number: CheckNumber::new("1170").unwrap(),
+            amount: Money::from_cents(25_000),
+            balance: Money::from_cents(100_000),
         };
-        let services = MockBankAccountServices::default();
-        services.set_validate_check_response(Ok(()));
-        let services = BankAccountServices::new(Box::new(services));
-        let command = BankAccountCommand::WriteCheck {
-            check_number: "1170".to_string(),
-            amount: 100.0,
+        let command = BankAccountCommand::VoidCheck {
+            check_number: CheckNumber::new("1170").unwrap(),
         };
 
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
         AccountTestFramework::with(services)
-            .given(vec![previous])
+            .given(vec![account_opened(), deposited, written_check()])
             .when(command)
             .then_expect_events(vec![expected]);
     }
 
     #[test]
-    fn test_wrote_check_bad_check() {
-        let previous = BankAccountEvent::CustomerDepositedMoney {
-            amount: 200.0,
-            balance: 200.0,
+    fn test_void_unknown_check() {
+        let command = BankAccountCommand::VoidCheck {
+            check_number: CheckNumber::new("1171").unwrap(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), written_check()])
+            .when(command)
+            .then_expect_error_message("check not found");
+    }
+
+    #[test]
+    fn test_void_check_twice() {
+        let voided = BankAccountEvent::CheckVoided {
+            check_number: CheckNumber::new("1170").unwrap(),
+            amount: Money::from_cents(25_000),
+            balance: Money::from_cents(100_000),
+        };
+        let command = BankAccountCommand::VoidCheck {
+            check_number: CheckNumber::new("1170").unwrap(),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened(), written_check(), voided])
+            .when(command)
+            .then_expect_error_message("check already voided");
+    }
+
+    fn overdraft_policy() -> BankAccountEvent {
+        BankAccountEvent::OverdraftPolicySet {
+            limit: Money::from_cents(50_000),
+            fee: Money::from_cents(3_500),
+        }
+    }
+
+    #[test]
+    fn test_withdrawal_within_overdraft_limit_charges_fee() {
+        let deposited = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(10_000),
+            balance: Money::from_cents(10_000),
         };
         let services = MockBankAccountServices::default();
-        services.set_validate_check_response(Err(CheckingError));
-        let services = BankAccountServices::new(Box::new(services));
-        let command = BankAccountCommand::WriteCheck {
-            check_number: "1170".to_string(),
-            amount: 100.0,
+        services.set_atm_withdrawal_response(Ok(()));
+        let command = BankAccountCommand::WithdrawMoney {
+            amount: Money::from_cents(30_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            authorized_by: None,
+            currency: "USD".to_string(),
         };
+        let expected = vec![
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(30_000),
+                balance: Money::from_cents(-20_000),
+                at: test_clock(),
+            },
+            BankAccountEvent::OverdraftFeeCharged {
+                fee: Money::from_cents(3_500),
+                balance: Money::from_cents(-23_500),
+            },
+        ];
 
+        let services = BankAccountServices::new(Box::new(services)).with_clock(test_clock);
         AccountTestFramework::with(services)
-            .given(vec![previous])
+            .given(vec![account_opened(), overdraft_policy(), deposited])
             .when(command)
-            .then_expect_error_message("check invalid");
+            .then_expect_events(expected);
     }
 
     #[test]
-    fn test_wrote_check_funds_not_available() {
+    fn test_check_beyond_overdraft_limit() {
+        let deposited = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(10_000),
+            balance: Money::from_cents(10_000),
+        };
+        // Within the limit, but not once the fee is charged.
         let command = BankAccountCommand::WriteCheck {
-            check_number: "1170".to_string(),
-            amount: 100.0,
+            check_number: CheckNumber::new("1170").unwrap(),
+            amount: Money::from_cents(58_000),
+            payee: None,
+            currency: "USD".to_string(),
         };
 
         let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
         AccountTestFramework::with(services)
-            .given_no_previous_events()
+            .given(vec![account_opened(), overdraft_policy(), deposited])
             .when(command)
-            .then_expect_error_message("funds not available")
+            .then_expect_error_message("funds not available");
+    }
+
+    #[test]
+    fn test_set_overdraft_policy_negative_limit() {
+        let command = BankAccountCommand::SetOverdraftPolicy {
+            limit: Money::from_cents(-50_000),
+            fee: Money::from_cents(3_500),
+        };
+
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        AccountTestFramework::with(services)
+            .given(vec![account_opened()])
+            .when(command)
+            .then_expect_error_message("overdraft limit cannot be negative");
+    }
+
+    // Runs `handle` against the account, applies the emitted events to a clone
+    // and asserts that the clone ends up with the balance the events claim.
+    async fn assert_apply_reproduces_balance(
+        account: &BankAccount,
+        command: BankAccountCommand,
+        services: &BankAccountServices,
+    ) {
+        let events = account.handle(command, services).await.unwrap();
+        assert!(!events.is_empty(), "command emitted no events");
+        let mut replayed = account.clone();
+        for event in events {
+            let stated = event.balance();
+            replayed.apply(event);
+            if let Some(balance) = stated {
+                assert_eq!(replayed.balance, balance);
+            }
+        }
+    }
+
+    fn funded_account() -> BankAccount {
+        let mut account = BankAccount::default();
+        account.apply(BankAccountEvent::AccountOpened {
+            account_id: "ACCT-1".to_string(),
+            currency: Some("USD".to_string()),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
+        });
+        account.apply(BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(50_000),
+            balance: Money::from_cents(50_000),
+        });
+        account
+    }
+
+    #[tokio::test]
+    async fn test_deposit_apply_reproduces_balance() {
+        let services = BankAccountServices::new(Box::new(MockBankAccountServices::default()));
+        let command = BankAccountCommand::DepositMoney {
+            amount: Money::from_cents(20_000),
+            currency: "USD".to_string(),
+        };
+        assert_apply_reproduces_balance(&funded_account(), command, &services).await;
+    }
+
+    #[tokio::test]
+    async fn test_withdraw_apply_reproduces_balance() {
+        let mock = MockBankAccountServices::default();
+        mock.set_atm_withdrawal_response(Ok(()));
+        let services = BankAccountServices::new(Box::new(mock));
+        let command = BankAccountCommand::WithdrawMoney {
+            amount: Money::from_cents(12_000),
+            atm_id: "ATM34f1ba3c".to_string(),
+            authorized_by: None,
+            currency: "USD".to_string(),
+        };
+        assert_apply_reproduces_balance(&funded_account(), command, &services).await;
+    }
+
+    #[tokio::test]
+    async fn test_write_check_apply_reproduces_balance() {
+        let mock = MockBankAccountServices::default();
+        mock.set_validate_check_response(Ok(()));
+        let services = BankAccountServices::new(Box::new(mock));
+        let command = BankAccountCommand::WriteCheck {
+            check_number: CheckNumber::new("1170").unwrap(),
+            amount: Money::from_cents(7_500),
+            payee: None,
+            currency: "USD".to_string(),
+        };
+        assert_apply_reproduces_balance(&funded_account(), command, &services).await;
     }
 
     pub struct MockBankAccountServices {
@@ -330,7 +4018,7 @@ mod aggregate_tests {
 
     #[async_trait]
     impl BankAccountApi for MockBankAccountServices {
-        async fn atm_withdrawal(&self, _atm_id: &str, _amount: f64) -> Result<(), AtmError> {
+        async fn atm_withdrawal(&self, _atm_id: &str, _amount: Money) -> Result<(), AtmError> {
             self.atm_withdrawal_response.lock().unwrap().take().unwrap()
         }
 
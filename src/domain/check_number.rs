@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+use crate::domain::errors::BankAccountError;
+
+// The longest check number accepted when no explicit length is given.
+pub const DEFAULT_CHECK_NUMBER_LENGTH: usize = 10;
+
+// A check number is a short run of digits printed on the check itself.
+// The value is validated by `new`, which the CLI parses with and `WriteCheck`
+// checks again, so a newly written check always has a valid number.
+// Serialization is unchanged from the plain `String` this replaces, and a
+// stored value is not validated when deserialized, so events written before
+// check numbers were validated, e.g., with "A-17", can still be loaded.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CheckNumber(String);
+
+impl CheckNumber {
+    pub fn new(value: &str) -> Result<Self, BankAccountError> {
+        Self::with_max_length(value, DEFAULT_CHECK_NUMBER_LENGTH)
+    }
+
+    pub fn with_max_length(value: &str, max_length: usize) -> Result<Self, BankAccountError> {
+        if value.is_empty() || !value.chars().all(|c| c.is_ascii_digit()) {
+            return Err("check number must be numeric".into());
+        }
+        if value.len() > max_length {
+            return Err("check number too long".into());
+        }
+        Ok(Self(value.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for CheckNumber {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod check_number_tests {
+    use crate::domain::check_number::CheckNumber;
+
+    #[test]
+    fn test_valid_check_number() {
+        let check_number = CheckNumber::new("1170").unwrap();
+        assert_eq!(check_number.as_str(), "1170");
+        assert_eq!(serde_json::to_string(&check_number).unwrap(), "\"1170\"");
+    }
+
+    #[test]
+    fn test_non_numeric_check_number() {
+        let err = CheckNumber::new("11a0").unwrap_err();
+        assert_eq!(err.to_string(), "check number must be numeric");
+    }
+
+    #[test]
+    fn test_stored_check_number_is_not_validated() {
+        let check_number: CheckNumber = serde_json::from_str("\"A-17\"").unwrap();
+        assert_eq!(check_number.as_str(), "A-17");
+    }
+
+    #[test]
+    fn test_check_number_too_long() {
+        let err = CheckNumber::with_max_length("117000", 4).unwrap_err();
+        assert_eq!(err.to_string(), "check number too long");
+    }
+}
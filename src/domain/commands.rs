@@ -1,10 +1,219 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
+use crate::domain::check_number::CheckNumber;
+use crate::domain::events::{KycStatus, StatementChannel};
+use crate::domain::money::{deserialize_exact, Money};
+
+// Amounts are read with `deserialize_exact`, so that one given in a request is
+// rejected rather than rounded when it is not a whole number of cents.
 #[derive(Debug, Serialize, Deserialize)]
 pub enum BankAccountCommand {
-    OpenAccount { account_id: String },
-    DepositMoney { amount: f64 },
-    WithdrawMoney { amount: f64, atm_id: String },
-    WriteCheck { check_number: String, amount: f64 },
-    IssueBonus { bonus_id: String,  amount: f64},
+    // The currency is an ISO 4217 code, e.g., "USD". Deposits, withdrawals and
+    // checks must be in the currency of the account.
+    OpenAccount {
+        account_id: String,
+        currency: String,
+        holder_name: String,
+        email: Option<String>,
+    },
+    DepositMoney {
+        #[serde(deserialize_with = "deserialize_exact")]
+        amount: Money,
+        currency: String,
+    },
+    // Large withdrawals from a joint account must name the joint holder authorizing them.
+    WithdrawMoney {
+        #[serde(deserialize_with = "deserialize_exact")]
+        amount: Money,
+        atm_id: String,
+        authorized_by: Option<String>,
+        currency: String,
+    },
+    DepositCheckImage {
+        #[serde(deserialize_with = "deserialize_exact")]
+        amount: Money,
+        front_image_ref: String,
+        back_image_ref: String,
+        currency: String,
+    },
+    // A check deposit, identified by its front image, either clears and may then
+    // be withdrawn or is rejected and taken back out of the balance.
+    ClearCheckDeposit {
+        front_image_ref: String,
+    },
+    RejectCheckDeposit {
+        front_image_ref: String,
+        reason: String,
+    },
+    WithdrawAtAtm {
+        #[serde(deserialize_with = "deserialize_exact")]
+        amount: Money,
+        atm_id: String,
+        location: String,
+        authorized_by: Option<String>,
+    },
+    // A point-of-sale purchase with cash back, debited as a single total.
+    WithdrawWithCashback {
+        #[serde(deserialize_with = "deserialize_exact")]
+        purchase_amount: Money,
+        #[serde(deserialize_with = "deserialize_exact")]
+        cashback_amount: Money,
+        merchant: String,
+    },
+    WriteCheck {
+        check_number: CheckNumber,
+        #[serde(deserialize_with = "deserialize_exact")]
+        amount: Money,
+        payee: Option<String>,
+        currency: String,
+    },
+    IssueBonus {
+        bonus_id: String,
+        #[serde(deserialize_with = "deserialize_exact")]
+        amount: Money,
+        currency: String,
+    },
+    ProposeReconciliationAdjustment {
+        proposal_id: String,
+        proposer: String,
+        #[serde(deserialize_with = "deserialize_exact")]
+        delta: Money,
+        reason: String,
+    },
+    ApproveReconciliationAdjustment { proposal_id: String, approver: String },
+    DisburseLoan {
+        loan_id: String,
+        #[serde(deserialize_with = "deserialize_exact")]
+        amount: Money,
+        currency: String,
+    },
+    AddAccountNote { note: String },
+    AddJointHolder { holder_id: String },
+    EnrollPaperless,
+    DisenrollPaperless,
+    // When no channel is given the statement is delivered by the channel that
+    // matches the account's paperless setting.
+    RecordStatementDelivery {
+        statement_id: String,
+        channel: Option<StatementChannel>,
+    },
+    RecordKyc {
+        status: KycStatus,
+        verified_at: DateTime<Utc>,
+    },
+    SetSpendAlert {
+        #[serde(deserialize_with = "deserialize_exact")]
+        threshold: Money,
+    },
+    // Warns the customer whenever a withdrawal or check takes the balance below `threshold`.
+    SetLowBalanceThreshold {
+        #[serde(deserialize_with = "deserialize_exact")]
+        threshold: Money,
+    },
+    SchedulePayment {
+        payee_id: String,
+        #[serde(deserialize_with = "deserialize_exact")]
+        amount: Money,
+        pay_date: NaiveDate,
+    },
+    SendWire {
+        #[serde(deserialize_with = "deserialize_exact")]
+        amount: Money,
+        beneficiary: String,
+        #[serde(deserialize_with = "deserialize_exact")]
+        fee: Money,
+    },
+    // A transfer to another account at this bank, run by `book_transfer` which
+    // also credits the destination and reverses the debit if that fails.
+    // A transfer is only made between accounts holding the same currency.
+    BookTransfer {
+        to_account_id: String,
+        #[serde(deserialize_with = "deserialize_exact")]
+        amount: Money,
+        currency: String,
+    },
+    ReceiveBookTransfer {
+        from_account_id: String,
+        #[serde(deserialize_with = "deserialize_exact")]
+        amount: Money,
+        currency: String,
+    },
+    ReverseBookTransfer {
+        to_account_id: String,
+        #[serde(deserialize_with = "deserialize_exact")]
+        amount: Money,
+    },
+    // Flags or clears the event at `target_sequence` for anti-money-laundering review.
+    FlagSuspicious {
+        target_sequence: usize,
+        reason: String,
+    },
+    ClearSuspiciousFlag {
+        target_sequence: usize,
+    },
+    // Records a direct debit returned unpaid by the counterparty, to be retried on `retry_date`.
+    ReturnDirectDebit {
+        mandate_id: String,
+        #[serde(deserialize_with = "deserialize_exact")]
+        amount: Money,
+        retry_date: NaiveDate,
+        currency: String,
+    },
+    // Retries every returned direct debit due on or before `as_of` that the account can fund.
+    ProcessDirectDebitRetries {
+        as_of: NaiveDate,
+    },
+    // Pays every scheduled payment due on or before `as_of`, earliest first.
+    ProcessScheduledPayments {
+        as_of: NaiveDate,
+    },
+    // Only an account with a zero balance can be closed.
+    CloseAccount {
+        account_id: String,
+    },
+    // Records the currency of an account opened before accounts carried one,
+    // ignored once the account has a currency.
+    BackfillCurrency {
+        currency: String,
+    },
+    // Accrues simple interest on the current balance for `days` days.
+    AddInterest {
+        annual_rate: f64,
+        days: u32,
+    },
+    // Cancels a written check before it clears, crediting its amount back.
+    VoidCheck {
+        check_number: CheckNumber,
+    },
+    // Allows withdrawals and checks to overdraw the account by up to `limit`,
+    // charging `fee` for each one that does. A zero limit disallows overdrafts.
+    SetOverdraftPolicy {
+        #[serde(deserialize_with = "deserialize_exact")]
+        limit: Money,
+        #[serde(deserialize_with = "deserialize_exact")]
+        fee: Money,
+    },
+    // Runs `command` at most once for a client-supplied `command_id`, so that a
+    // retried request does not, e.g., deposit the same money twice. A repeat of
+    // one of the account's recent command ids is accepted but changes nothing.
+    Idempotent {
+        command_id: Uuid,
+        command: Box<BankAccountCommand>,
+    },
+}
+
+impl BankAccountCommand {
+    // Whether only `book_transfer` may issue this command, as one step of a
+    // transfer it is coordinating. These credit the account without any funds
+    // leaving another, so they are never accepted from a client.
+    pub fn is_book_transfer_step(&self) -> bool {
+        match self {
+            BankAccountCommand::ReceiveBookTransfer { .. }
+            | BankAccountCommand::ReverseBookTransfer { .. } => true,
+            BankAccountCommand::Idempotent { command, .. } => command.is_book_transfer_step(),
+            _ => false,
+        }
+    }
 }
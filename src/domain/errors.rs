@@ -0,0 +1,44 @@
+use std::fmt::{Display, Formatter};
+
+use crate::domain::money::Money;
+
+// The reasons a command against an account is rejected. The messages are those
+// that callers have come to rely on, e.g., an `ApiError` carries the `Display`
+// output, so rules that callers match on are their own variants while every
+// other rule is `Rejected` with its message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BankAccountError {
+    // A debit of `requested` was refused since only `available` could be
+    // debited, i.e., the balance less any funds held for pending deposits plus
+    // whatever the overdraft limit allows.
+    InsufficientFunds { requested: Money, available: Money },
+    AccountClosed,
+    AccountNotOpen,
+    InvalidAmount,
+    // The amount would take the balance, or a total, beyond what `Money` can hold.
+    AmountOutOfRange,
+    CurrencyMismatch,
+    Rejected(String),
+}
+
+impl From<&str> for BankAccountError {
+    fn from(msg: &str) -> Self {
+        Self::Rejected(msg.to_string())
+    }
+}
+
+impl Display for BankAccountError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BankAccountError::InsufficientFunds { .. } => write!(f, "funds not available"),
+            BankAccountError::AccountClosed => write!(f, "account is closed"),
+            BankAccountError::AccountNotOpen => write!(f, "account not open"),
+            BankAccountError::InvalidAmount => write!(f, "amount must be positive"),
+            BankAccountError::AmountOutOfRange => write!(f, "amount out of range"),
+            BankAccountError::CurrencyMismatch => write!(f, "currency mismatch"),
+            BankAccountError::Rejected(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for BankAccountError {}
@@ -1,29 +1,281 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use cqrs_es::DomainEvent;
 use serde::{Deserialize, Serialize};
-use std::fmt::{Debug, Display, Formatter};
+use std::fmt::Debug;
+use uuid::Uuid;
+
+use crate::domain::check_number::CheckNumber;
+use crate::domain::money::Money;
+use crate::domain::upcast::schema_version;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum BankAccountEvent {
     AccountOpened {
         account_id: String,
+        // Not recorded for accounts opened before their currency was, see
+        // `CurrencyBackfilled`.
+        #[serde(default)]
+        currency: Option<String>,
+        // Empty for accounts opened before the holder was recorded.
+        #[serde(default)]
+        holder_name: String,
+        #[serde(default)]
+        email: Option<String>,
     },
     CustomerDepositedMoney {
-        amount: f64,
-        balance: f64,
+        amount: Money,
+        balance: Money,
     },
     CustomerWithdrewCash {
-        amount: f64,
-        balance: f64,
+        amount: Money,
+        balance: Money,
+        // When the withdrawal was made, for the daily withdrawal limit. Withdrawals
+        // recorded before this was added count towards no day's limit.
+        #[serde(default)]
+        at: DateTime<Utc>,
+    },
+    CheckImageDeposited {
+        amount: Money,
+        balance: Money,
+        front_image_ref: String,
+        back_image_ref: String,
+    },
+    CheckDepositCleared {
+        front_image_ref: String,
+        amount: Money,
+    },
+    CheckDepositRejected {
+        front_image_ref: String,
+        amount: Money,
+        balance: Money,
+        reason: String,
+    },
+    AtmWithdrawal {
+        amount: Money,
+        balance: Money,
+        atm_id: String,
+        location: String,
+        // As for `CustomerWithdrewCash`, when the withdrawal was made.
+        #[serde(default)]
+        at: DateTime<Utc>,
+    },
+    // Only the cash back counts towards the daily withdrawal limit.
+    PurchaseWithCashback {
+        purchase_amount: Money,
+        cashback_amount: Money,
+        merchant: String,
+        balance: Money,
+        #[serde(default)]
+        at: DateTime<Utc>,
     },
     CustomerWroteCheck {
-        check_number: String,
-        amount: f64,
-        balance: f64,
+        check_number: CheckNumber,
+        amount: Money,
+        balance: Money,
+        payee: Option<String>,
     },
     IssuedBonus {
         bonus_id: String,
-        amount: f64,
-        balance: f64,
+        amount: Money,
+        balance: Money,
+    },
+    AdjustmentProposed {
+        proposal_id: String,
+        proposer: String,
+        delta: Money,
+        reason: String,
+    },
+    AdjustmentApproved {
+        proposal_id: String,
+        approver: String,
+        delta: Money,
+        balance: Money,
+    },
+    LoanDisbursed {
+        loan_id: String,
+        amount: Money,
+        balance: Money,
+    },
+    AccountNoteAdded {
+        note: String,
+        timestamp: DateTime<Utc>,
+    },
+    JointHolderAdded {
+        holder_id: String,
+    },
+    PaperlessEnrolled,
+    PaperlessDisenrolled,
+    StatementDelivered {
+        statement_id: String,
+        channel: StatementChannel,
+    },
+    KycStatusRecorded {
+        status: KycStatus,
+        verified_at: DateTime<Utc>,
+    },
+    SpendAlertSet {
+        threshold: Money,
+    },
+    // Recorded alongside any single debit larger than the account's spend alert threshold.
+    SpendAlertTriggered {
+        amount: Money,
+        threshold: Money,
+    },
+    LowBalanceThresholdSet {
+        threshold: Money,
+    },
+    // Recorded alongside a withdrawal or check that takes the balance from at or
+    // above the low balance threshold to below it, but not while it stays below.
+    LowBalanceWarningTriggered {
+        balance: Money,
+        threshold: Money,
+    },
+    WireSent {
+        amount: Money,
+        fee: Money,
+        balance: Money,
+        beneficiary: String,
+    },
+    BookTransferSent {
+        to_account_id: String,
+        amount: Money,
+        balance: Money,
+    },
+    BookTransferReceived {
+        from_account_id: String,
+        amount: Money,
+        balance: Money,
+    },
+    BookTransferReversed {
+        to_account_id: String,
+        amount: Money,
+        balance: Money,
+    },
+    TransactionFlagged {
+        target_sequence: usize,
+        reason: String,
+    },
+    FlagCleared {
+        target_sequence: usize,
+    },
+    DirectDebitReturned {
+        mandate_id: String,
+        amount: Money,
+        retry_date: NaiveDate,
+        balance: Money,
+    },
+    DirectDebitRetried {
+        mandate_id: String,
+        amount: Money,
+        balance: Money,
+    },
+    PaymentScheduled {
+        payee_id: String,
+        amount: Money,
+        pay_date: NaiveDate,
+    },
+    ScheduledPaymentMade {
+        payee_id: String,
+        amount: Money,
+        pay_date: NaiveDate,
+        balance: Money,
+    },
+    // A scheduled payment that could not be made for lack of funds, it is not retried.
+    PaymentFailed {
+        payee_id: String,
+        amount: Money,
+        pay_date: NaiveDate,
+    },
+    AccountClosed {
+        account_id: String,
+    },
+    CurrencyBackfilled {
+        currency: String,
+    },
+    InterestAccrued {
+        amount: Money,
+        balance: Money,
+    },
+    CheckVoided {
+        check_number: CheckNumber,
+        amount: Money,
+        balance: Money,
+    },
+    OverdraftPolicySet {
+        limit: Money,
+        fee: Money,
+    },
+    // Recorded alongside a withdrawal or check that overdraws the account.
+    OverdraftFeeCharged {
+        fee: Money,
+        balance: Money,
+    },
+    // Recorded after the events of an `Idempotent` command, marking its id as processed.
+    CommandProcessed {
+        command_id: Uuid,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum StatementChannel {
+    Email,
+    Mail,
+}
+
+// The outcome of the latest know-your-customer check on the account holder.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum KycStatus {
+    Pending,
+    Verified,
+    Rejected,
+}
+
+impl BankAccountEvent {
+    // The account balance after this event, for those events that change it.
+    pub fn balance(&self) -> Option<Money> {
+        match self {
+            BankAccountEvent::CustomerDepositedMoney { balance, .. }
+            | BankAccountEvent::CustomerWithdrewCash { balance, .. }
+            | BankAccountEvent::CheckImageDeposited { balance, .. }
+            | BankAccountEvent::CheckDepositRejected { balance, .. }
+            | BankAccountEvent::AtmWithdrawal { balance, .. }
+            | BankAccountEvent::PurchaseWithCashback { balance, .. }
+            | BankAccountEvent::CustomerWroteCheck { balance, .. }
+            | BankAccountEvent::IssuedBonus { balance, .. }
+            | BankAccountEvent::AdjustmentApproved { balance, .. }
+            | BankAccountEvent::LoanDisbursed { balance, .. }
+            | BankAccountEvent::WireSent { balance, .. }
+            | BankAccountEvent::BookTransferSent { balance, .. }
+            | BankAccountEvent::BookTransferReceived { balance, .. }
+            | BankAccountEvent::BookTransferReversed { balance, .. }
+            | BankAccountEvent::DirectDebitReturned { balance, .. }
+            | BankAccountEvent::DirectDebitRetried { balance, .. }
+            | BankAccountEvent::ScheduledPaymentMade { balance, .. }
+            | BankAccountEvent::InterestAccrued { balance, .. }
+            | BankAccountEvent::CheckVoided { balance, .. }
+            | BankAccountEvent::OverdraftFeeCharged { balance, .. } => Some(*balance),
+            BankAccountEvent::AccountOpened { .. }
+            | BankAccountEvent::CheckDepositCleared { .. }
+            | BankAccountEvent::AdjustmentProposed { .. }
+            | BankAccountEvent::AccountNoteAdded { .. }
+            | BankAccountEvent::JointHolderAdded { .. }
+            | BankAccountEvent::PaperlessEnrolled
+            | BankAccountEvent::PaperlessDisenrolled
+            | BankAccountEvent::StatementDelivered { .. }
+            | BankAccountEvent::KycStatusRecorded { .. }
+            | BankAccountEvent::SpendAlertSet { .. }
+            | BankAccountEvent::SpendAlertTriggered { .. }
+            | BankAccountEvent::LowBalanceThresholdSet { .. }
+            | BankAccountEvent::LowBalanceWarningTriggered { .. }
+            | BankAccountEvent::PaymentScheduled { .. }
+            | BankAccountEvent::PaymentFailed { .. }
+            | BankAccountEvent::TransactionFlagged { .. }
+            | BankAccountEvent::FlagCleared { .. }
+            | BankAccountEvent::AccountClosed { .. }
+            | BankAccountEvent::CurrencyBackfilled { .. }
+            | BankAccountEvent::OverdraftPolicySet { .. }
+            | BankAccountEvent::CommandProcessed { .. } => None,
+        }
     }
 }
 
@@ -33,29 +285,50 @@ impl DomainEvent for BankAccountEvent {
             BankAccountEvent::AccountOpened { .. } => "AccountOpened".to_string(),
             BankAccountEvent::CustomerDepositedMoney { .. } => "CustomerDepositedMoney".to_string(),
             BankAccountEvent::CustomerWithdrewCash { .. } => "CustomerWithdrewCash".to_string(),
+            BankAccountEvent::CheckImageDeposited { .. } => "CheckImageDeposited".to_string(),
+            BankAccountEvent::CheckDepositCleared { .. } => "CheckDepositCleared".to_string(),
+            BankAccountEvent::CheckDepositRejected { .. } => "CheckDepositRejected".to_string(),
+            BankAccountEvent::AtmWithdrawal { .. } => "AtmWithdrawal".to_string(),
+            BankAccountEvent::PurchaseWithCashback { .. } => "PurchaseWithCashback".to_string(),
             BankAccountEvent::CustomerWroteCheck { .. } => "CustomerWroteCheck".to_string(),
             BankAccountEvent::IssuedBonus { .. } => "IssuedBonus".to_string(),
+            BankAccountEvent::AdjustmentProposed { .. } => "AdjustmentProposed".to_string(),
+            BankAccountEvent::AdjustmentApproved { .. } => "AdjustmentApproved".to_string(),
+            BankAccountEvent::LoanDisbursed { .. } => "LoanDisbursed".to_string(),
+            BankAccountEvent::AccountNoteAdded { .. } => "AccountNoteAdded".to_string(),
+            BankAccountEvent::JointHolderAdded { .. } => "JointHolderAdded".to_string(),
+            BankAccountEvent::PaperlessEnrolled => "PaperlessEnrolled".to_string(),
+            BankAccountEvent::PaperlessDisenrolled => "PaperlessDisenrolled".to_string(),
+            BankAccountEvent::StatementDelivered { .. } => "StatementDelivered".to_string(),
+            BankAccountEvent::KycStatusRecorded { .. } => "KycStatusRecorded".to_string(),
+            BankAccountEvent::SpendAlertSet { .. } => "SpendAlertSet".to_string(),
+            BankAccountEvent::SpendAlertTriggered { .. } => "SpendAlertTriggered".to_string(),
+            BankAccountEvent::LowBalanceThresholdSet { .. } => "LowBalanceThresholdSet".to_string(),
+            BankAccountEvent::LowBalanceWarningTriggered { .. } => {
+                "LowBalanceWarningTriggered".to_string()
+            }
+            BankAccountEvent::WireSent { .. } => "WireSent".to_string(),
+            BankAccountEvent::BookTransferSent { .. } => "BookTransferSent".to_string(),
+            BankAccountEvent::BookTransferReceived { .. } => "BookTransferReceived".to_string(),
+            BankAccountEvent::BookTransferReversed { .. } => "BookTransferReversed".to_string(),
+            BankAccountEvent::TransactionFlagged { .. } => "TransactionFlagged".to_string(),
+            BankAccountEvent::FlagCleared { .. } => "FlagCleared".to_string(),
+            BankAccountEvent::DirectDebitReturned { .. } => "DirectDebitReturned".to_string(),
+            BankAccountEvent::DirectDebitRetried { .. } => "DirectDebitRetried".to_string(),
+            BankAccountEvent::PaymentScheduled { .. } => "PaymentScheduled".to_string(),
+            BankAccountEvent::ScheduledPaymentMade { .. } => "ScheduledPaymentMade".to_string(),
+            BankAccountEvent::PaymentFailed { .. } => "PaymentFailed".to_string(),
+            BankAccountEvent::AccountClosed { .. } => "AccountClosed".to_string(),
+            BankAccountEvent::CurrencyBackfilled { .. } => "CurrencyBackfilled".to_string(),
+            BankAccountEvent::InterestAccrued { .. } => "InterestAccrued".to_string(),
+            BankAccountEvent::CheckVoided { .. } => "CheckVoided".to_string(),
+            BankAccountEvent::OverdraftPolicySet { .. } => "OverdraftPolicySet".to_string(),
+            BankAccountEvent::OverdraftFeeCharged { .. } => "OverdraftFeeCharged".to_string(),
+            BankAccountEvent::CommandProcessed { .. } => "CommandProcessed".to_string(),
         }
     }
 
     fn event_version(&self) -> String {
-        "1.0".to_string()
+        format!("{}.0", schema_version(&self.event_type()))
     }
 }
-
-#[derive(Debug)]
-pub struct BankAccountError(String);
-
-impl From<&str> for BankAccountError {
-    fn from(msg: &str) -> Self {
-        Self(msg.to_string())
-    }
-}
-
-impl Display for BankAccountError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
-    }
-}
-
-impl std::error::Error for BankAccountError {}
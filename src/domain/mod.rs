@@ -1,3 +1,7 @@
 pub mod aggregate;
+pub mod check_number;
 pub mod commands;
+pub mod errors;
 pub mod events;
+pub mod money;
+pub mod upcast;
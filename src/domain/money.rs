@@ -0,0 +1,277 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt::{Display, Formatter};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+use crate::domain::errors::BankAccountError;
+
+// An amount of money held as a whole number of cents so that repeated
+// deposits and withdrawals never accumulate rounding error.
+// Money is serialized as a decimal string with exactly two decimal places,
+// e.g., "743.72", so that the value is exact in any JSON parser. A JSON number
+// is also accepted when deserializing so that events and requests written
+// before this type existed can still be read. A stored number is rounded to
+// the nearest cent, as amounts held as `f64` drifted from whole cents, while a
+// command rejects one with more than two decimal places (see
+// `deserialize_exact`). A string with more than two is rejected either way.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(try_from = "MoneyRepr", into = "String")]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub const fn from_cents(cents: i64) -> Self {
+        Self(cents)
+    }
+
+    pub fn cents(&self) -> i64 {
+        self.0
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.0 > 0
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.0 < 0
+    }
+
+    // As `+` and `-`, but `None` rather than overflowing. Commands use these for
+    // any amount taken from a request, which may be as large as `i64::MAX` cents.
+    pub fn checked_add(self, other: Money) -> Option<Money> {
+        self.0.checked_add(other.0).map(Money)
+    }
+
+    pub fn checked_sub(self, other: Money) -> Option<Money> {
+        self.0.checked_sub(other.0).map(Money)
+    }
+}
+
+impl Money {
+    // The nearest whole number of cents to `value` dollars.
+    fn rounded(value: f64) -> Result<Self, BankAccountError> {
+        let cents = value * 100.0;
+        if !cents.is_finite() || cents.abs() > i64::MAX as f64 {
+            return Err("amount out of range".into());
+        }
+        Ok(Self(cents.round() as i64))
+    }
+}
+
+impl TryFrom<f64> for Money {
+    type Error = BankAccountError;
+
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        let money = Money::rounded(value)?;
+        // Allow for the binary representation of decimal values, e.g., 0.1 * 100.0
+        // is slightly more than 10.
+        if (value * 100.0 - money.0 as f64).abs() > 1e-6 {
+            return Err("amount has more than two decimal places".into());
+        }
+        Ok(money)
+    }
+}
+
+impl From<Money> for f64 {
+    fn from(money: Money) -> Self {
+        money.0 as f64 / 100.0
+    }
+}
+
+impl FromStr for Money {
+    type Err = BankAccountError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (negative, unsigned) = match value.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, value),
+        };
+        let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        let digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if whole.is_empty() || !digits(whole) || !digits(fraction) {
+            return Err("amount must be a decimal number".into());
+        }
+        if fraction.len() > 2 {
+            return Err("amount has more than two decimal places".into());
+        }
+        let cents = format!("{}{:0<2}", whole, fraction)
+            .parse::<i64>()
+            .map_err(|_| BankAccountError::from("amount out of range"))?;
+        Ok(Self(if negative { -cents } else { cents }))
+    }
+}
+
+impl Display for Money {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let cents = self.0.unsigned_abs();
+        // Padded so that widths and alignment can be used when formatting.
+        f.pad(&format!("{}{}.{:02}", sign, cents / 100, cents % 100))
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        Money(self.0 - other.0)
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.0 += other.0;
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        self.0 -= other.0;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MoneyRepr {
+    Text(String),
+    Number(f64),
+}
+
+impl TryFrom<MoneyRepr> for Money {
+    type Error = BankAccountError;
+
+    fn try_from(value: MoneyRepr) -> Result<Self, Self::Error> {
+        match value {
+            MoneyRepr::Text(text) => text.parse(),
+            MoneyRepr::Number(number) => Money::rounded(number),
+        }
+    }
+}
+
+// Deserializes an amount given in a command, where a JSON number with more than
+// two decimal places is rejected rather than rounded as a stored one is.
+pub fn deserialize_exact<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Money, D::Error> {
+    match MoneyRepr::deserialize(deserializer)? {
+        MoneyRepr::Text(text) => text.parse(),
+        MoneyRepr::Number(number) => Money::try_from(number),
+    }
+    .map_err(serde::de::Error::custom)
+}
+
+impl From<Money> for String {
+    fn from(money: Money) -> Self {
+        money.to_string()
+    }
+}
+
+#[cfg(test)]
+mod money_tests {
+    use crate::domain::money::{deserialize_exact, Money};
+
+    #[test]
+    fn test_repeated_deposits_do_not_drift() {
+        let dime = Money::try_from(0.1).unwrap();
+        let total: Money = std::iter::repeat_n(dime, 1000).sum();
+        assert_eq!(total, Money::from_cents(10_000));
+
+        let float_total: f64 = std::iter::repeat_n(0.1, 1000).sum();
+        assert_ne!(float_total, 100.0);
+    }
+
+    #[test]
+    fn test_checked_arithmetic_near_i64_max() {
+        let max = Money::from_cents(i64::MAX);
+        assert_eq!(max.checked_add(Money::from_cents(1)), None);
+        assert_eq!(
+            max.checked_sub(Money::from_cents(1)),
+            Some(Money::from_cents(i64::MAX - 1))
+        );
+        assert_eq!(Money::from_cents(-2).checked_sub(max), None);
+    }
+
+    #[test]
+    fn test_more_than_two_decimal_places() {
+        let err = Money::try_from(12.345).unwrap_err();
+        assert_eq!(err.to_string(), "amount has more than two decimal places");
+        assert!("12.345".parse::<Money>().is_err());
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(
+            "743.72".parse::<Money>().unwrap(),
+            Money::from_cents(74_372)
+        );
+        assert_eq!("-0.5".parse::<Money>().unwrap(), Money::from_cents(-50));
+        assert_eq!("20".parse::<Money>().unwrap(), Money::from_cents(2_000));
+        assert!("12.a".parse::<Money>().is_err());
+        assert!(".50".parse::<Money>().is_err());
+        assert_eq!(Money::from_cents(-1_005).to_string(), "-10.05");
+    }
+
+    #[test]
+    fn test_serialization() {
+        let money = Money::from_cents(74_372);
+        assert_eq!(serde_json::to_string(&money).unwrap(), "\"743.72\"");
+        assert_eq!(serde_json::from_str::<Money>("\"743.72\"").unwrap(), money);
+        assert_eq!(serde_json::from_str::<Money>("743.72").unwrap(), money);
+        assert!(serde_json::from_str::<Money>("\"743.721\"").is_err());
+    }
+
+    #[test]
+    fn test_stored_number_is_rounded_to_the_nearest_cent() {
+        // A balance accumulated as `f64`, e.g., 0.1 + 0.2.
+        assert_eq!(
+            serde_json::from_str::<Money>("0.30000000000000004").unwrap(),
+            Money::from_cents(30)
+        );
+        assert_eq!(
+            serde_json::from_str::<Money>("743.726").unwrap(),
+            Money::from_cents(74_373)
+        );
+    }
+
+    #[test]
+    fn test_command_number_is_exact() {
+        let exact = |json: &str| deserialize_exact(&mut serde_json::Deserializer::from_str(json));
+        assert_eq!(exact("743.72").unwrap(), Money::from_cents(74_372));
+        assert!(exact("743.721").is_err());
+        assert!(exact("\"743.721\"").is_err());
+    }
+
+    #[test]
+    fn test_arithmetic_and_ordering() {
+        let balance = Money::from_cents(10_000);
+        let amount = Money::from_cents(12_050);
+        assert_eq!(balance - amount, Money::from_cents(-2_050));
+        assert!((balance - amount).is_negative());
+        assert!(amount > balance);
+        assert_eq!(balance + amount, Money::from_cents(22_050));
+    }
+}
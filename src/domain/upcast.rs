@@ -0,0 +1,153 @@
+use serde::de::Error;
+use serde_json::Value;
+
+use crate::domain::events::BankAccountEvent;
+
+// The current schema version of each event type. A version is bumped whenever
+// the stored shape of an event changes, along with a step in `upcast` that
+// brings the previous shape up to date. Every event type not listed here is
+// still at version 1.
+//
+// - `CustomerWroteCheck` 2: `check_number` is a string rather than a number.
+pub fn schema_version(event_type: &str) -> u16 {
+    match event_type {
+        "CustomerWroteCheck" => 2,
+        _ => 1,
+    }
+}
+
+// Reads the schema version from the `schema_version` metadata written with an
+// event, e.g., "2.0". Events written before versions were recorded are version 1.
+pub fn parse_schema_version(version: Option<&str>) -> serde_json::Result<u16> {
+    match version {
+        None => Ok(1),
+        Some(version) => version
+            .split('.')
+            .next()
+            .and_then(|major| major.parse().ok())
+            .ok_or_else(|| {
+                serde_json::Error::custom(format!("invalid schema version {}", version))
+            }),
+    }
+}
+
+// Deserializes an event stored at `version` of its schema, first bringing an
+// older shape up to date. An event from a version newer than this build knows
+// about is an error rather than being deserialized as best it can, since fields
+// it does not recognize would otherwise be silently dropped.
+pub fn upcast(mut raw: Value, version: u16) -> serde_json::Result<BankAccountEvent> {
+    let event_type = match &raw {
+        Value::Object(event) if event.len() == 1 => event.keys().next().unwrap().clone(),
+        _ => return Err(serde_json::Error::custom("event is not a single variant")),
+    };
+    let current = schema_version(&event_type);
+    if version == 0 || version > current {
+        return Err(serde_json::Error::custom(format!(
+            "unknown schema version {} of {}",
+            version, event_type
+        )));
+    }
+    if event_type == "CustomerWroteCheck" && version < 2 {
+        if let Some(check_number) = raw.pointer_mut("/CustomerWroteCheck/check_number") {
+            if let Value::Number(number) = check_number {
+                *check_number = Value::String(number.to_string());
+            }
+        }
+    }
+    serde_json::from_value(raw)
+}
+
+// Implemented by the events of an aggregate stored in EventStore, so that events
+// written in an older shape can still be loaded.
+pub trait Upcast: Sized {
+    fn upcast(raw: Value, version: u16) -> serde_json::Result<Self>;
+}
+
+impl Upcast for BankAccountEvent {
+    fn upcast(raw: Value, version: u16) -> serde_json::Result<Self> {
+        upcast(raw, version)
+    }
+}
+
+#[cfg(test)]
+mod upcast_tests {
+    use serde_json::json;
+
+    use crate::domain::check_number::CheckNumber;
+    use crate::domain::events::BankAccountEvent;
+    use crate::domain::money::Money;
+    use crate::domain::upcast::{parse_schema_version, upcast};
+
+    #[test]
+    fn test_upcast_v1_check_number() {
+        let raw = json!({
+            "CustomerWroteCheck": {
+                "check_number": 1170,
+                "amount": "250.00",
+                "balance": "750.00",
+                "payee": "Acme Plumbing"
+            }
+        });
+        assert_eq!(
+            upcast(raw, 1).unwrap(),
+            BankAccountEvent::CustomerWroteCheck {
+                check_number: CheckNumber::new("1170").unwrap(),
+                amount: Money::from_cents(25_000),
+                balance: Money::from_cents(75_000),
+                payee: Some("Acme Plumbing".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_legacy_check_number_is_loaded() {
+        let raw = json!({
+            "CustomerWroteCheck": {
+                "check_number": "A-17",
+                "amount": "250.00",
+                "balance": "750.00",
+                "payee": null
+            }
+        });
+        match upcast(raw, 1).unwrap() {
+            BankAccountEvent::CustomerWroteCheck { check_number, .. } => {
+                assert_eq!(check_number.as_str(), "A-17")
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+    }
+
+    #[test]
+    fn test_upcast_current_version_is_unchanged() {
+        let event = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(12_500),
+            balance: Money::from_cents(12_500),
+        };
+        let raw = serde_json::to_value(&event).unwrap();
+        assert_eq!(upcast(raw, 1).unwrap(), event);
+    }
+
+    #[test]
+    fn test_upcast_unknown_future_version() {
+        let raw = json!({
+            "CustomerWroteCheck": {
+                "check_number": "1170",
+                "amount": "250.00",
+                "balance": "750.00",
+                "payee": null
+            }
+        });
+        let err = upcast(raw, 3).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown schema version 3 of CustomerWroteCheck"
+        );
+    }
+
+    #[test]
+    fn test_parse_schema_version() {
+        assert_eq!(parse_schema_version(Some("2.0")).unwrap(), 2);
+        assert_eq!(parse_schema_version(None).unwrap(), 1);
+        assert!(parse_schema_version(Some("two")).is_err());
+    }
+}
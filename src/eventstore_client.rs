@@ -1,11 +1,234 @@
-use eventstore::Client;
+use std::collections::HashMap;
+use std::error::Error;
 
+use cqrs_es::{Aggregate, DomainEvent, EventEnvelope};
+use eventstore::{Client, ClientSettings, EventData, ReadStream, RecordedEvent};
+use futures::{Stream, TryStreamExt};
+
+use crate::domain::aggregate::BankAccount;
+use crate::domain::upcast::{parse_schema_version, Upcast};
+
+// Identifies this application as the writer of an event.
+const SOURCE_SERVICE: &str = env!("CARGO_PKG_NAME");
+
+// The EventStore started by `docker-compose up -d`, used when no connection
+// string is set in the environment.
+const DEFAULT_CONNECTION_STRING: &str =
+    "esdb://127.0.0.1:2113?tls=false&keepAliveTimeout=10000&keepAliveInterval=10000";
+const CONNECTION_STRING_VAR: &str = "ESDB_CONNECTION_STRING";
+
+// Connects to the EventStore named by `ESDB_CONNECTION_STRING`, or the local
+// default when it is not set. Must be called from within a tokio runtime.
 pub fn get_client() -> Result<Client, Box<dyn std::error::Error>> {
+    get_client_with_settings(&connection_string())
+}
 
-    let settings = "esdb://127.0.0.1:2113?tls=false&keepAliveTimeout=10000&keepAliveInterval=10000".parse()?;
+pub fn get_client_with_settings(conn: &str) -> Result<Client, Box<dyn std::error::Error>> {
+    let settings: ClientSettings = conn
+        .parse()
+        .map_err(|err| format!("invalid EventStore connection string: {}", err))?;
 
     let client = Client::new(settings)?;
-    
+
     Ok(client)
+}
+
+fn connection_string() -> String {
+    std::env::var(CONNECTION_STRING_VAR).unwrap_or_else(|_| DEFAULT_CONNECTION_STRING.to_string())
+}
+
+// Builds the EventStore representation of a committed event. The event metadata
+// (e.g., time, uri, user-agent and correlation id) is stored as EventStore custom
+// metadata along with the service that wrote the event and the schema version
+// of its payload.
+pub fn to_event_data<A: Aggregate>(event: &EventEnvelope<A>) -> serde_json::Result<EventData> {
+    let mut metadata = event.metadata.clone();
+    metadata.insert("source_service".to_string(), SOURCE_SERVICE.to_string());
+    metadata.insert("schema_version".to_string(), event.payload.event_version());
+    EventData::json(event.payload.event_type(), &event.payload)?.metadata_as_json(&metadata)
+}
+
+// The EventStore stream holding the events of an account, as named by
+// `EventStoreDbStore`, e.g., `account-ACCT-1`.
+fn stream_name(account_id: &str) -> String {
+    format!("{}-{}", BankAccount::aggregate_type(), account_id)
+}
+
+// Reads every event of an account, restoring the custom metadata written by
+// `to_event_data` into the envelope's metadata. An account without a stream yet
+// is treated as having no events.
+pub async fn read_events(
+    client: &Client,
+    account_id: &str,
+) -> Result<Vec<EventEnvelope<BankAccount>>, Box<dyn Error>> {
+    stream_events(client, account_id).try_collect().await
+}
+
+// As `read_events`, but yields the events one at a time as they are read from
+// EventStore rather than collecting them, so that very long streams can be
+// processed in constant memory. Nothing is read until the stream is polled.
+pub fn stream_events(
+    client: &Client,
+    account_id: &str,
+) -> impl Stream<Item = Result<EventEnvelope<BankAccount>, Box<dyn Error>>> {
+    let client = client.clone();
+    let account_id = account_id.to_string();
+    futures::stream::try_unfold(None, move |reader: Option<ReadStream>| {
+        let client = client.clone();
+        let account_id = account_id.clone();
+        async move {
+            let mut reader = match reader {
+                Some(reader) => reader,
+                None => {
+                    client
+                        .read_stream(stream_name(&account_id), &Default::default())
+                        .await?
+                }
+            };
+            let resolved = match reader.next().await {
+                Ok(Some(resolved)) => resolved,
+                Ok(None) | Err(eventstore::Error::ResourceNotFound) => return Ok(None),
+                Err(err) => return Err(err.into()),
+            };
+            let event = to_envelope(&account_id, resolved.get_original_event())?;
+            Ok(Some((event, Some(reader))))
+        }
+    })
+}
+
+// Restores a recorded event as an envelope for `aggregate_id`, upcasting a
+// payload written at an older schema version.
+pub(crate) fn to_envelope<A>(
+    aggregate_id: &str,
+    recorded: &RecordedEvent,
+) -> serde_json::Result<EventEnvelope<A>>
+where
+    A: Aggregate,
+    A::Event: Upcast,
+{
+    let metadata: HashMap<String, String> = if recorded.custom_metadata.is_empty() {
+        HashMap::new()
+    } else {
+        serde_json::from_slice(&recorded.custom_metadata)?
+    };
+    let version = parse_schema_version(metadata.get("schema_version").map(String::as_str))?;
+    let payload = A::Event::upcast(recorded.as_json()?, version)?;
+    Ok(EventEnvelope {
+        aggregate_id: aggregate_id.to_string(),
+        // EventStore revisions start at zero, aggregate sequences start at one.
+        sequence: recorded.revision as usize + 1,
+        payload,
+        metadata,
+    })
+}
+
+#[cfg(test)]
+mod connection_settings_tests {
+    use crate::eventstore_client::{
+        connection_string, get_client_with_settings, stream_name, DEFAULT_CONNECTION_STRING,
+    };
+
+    #[test]
+    fn test_stream_name() {
+        assert_eq!(stream_name("ACCT-1"), "account-ACCT-1");
+    }
+
+    #[test]
+    fn test_connection_string_defaults_to_localhost() {
+        std::env::remove_var("ESDB_CONNECTION_STRING");
+        assert_eq!(connection_string(), DEFAULT_CONNECTION_STRING);
+    }
+
+    #[tokio::test]
+    async fn test_malformed_connection_string() {
+        match get_client_with_settings("not a connection string") {
+            Ok(_) => panic!("a malformed connection string was accepted"),
+            Err(err) => assert!(err
+                .to_string()
+                .starts_with("invalid EventStore connection string")),
+        }
+    }
+}
+
+// These tests require a running EventStore instance (`docker-compose up -d`),
+// run them with `cargo test --features eventstore-integration`.
+#[cfg(all(test, feature = "eventstore-integration"))]
+mod eventstore_client_tests {
+    use cqrs_es::EventEnvelope;
+    use futures::TryStreamExt;
+    use std::collections::HashMap;
+
+    use crate::domain::aggregate::BankAccount;
+    use crate::domain::events::BankAccountEvent;
+    use crate::domain::money::Money;
+    use crate::eventstore_client::{
+        get_client, read_events, stream_events, stream_name, to_event_data,
+    };
+
+    #[tokio::test]
+    async fn test_append_and_read_event_metadata() {
+        let client = get_client().unwrap();
+        let account_id = format!("metadata-test-{}", chrono::Utc::now().timestamp_nanos());
+        let mut metadata = HashMap::new();
+        metadata.insert("correlation_id".to_string(), "corr-8812".to_string());
+        let event: EventEnvelope<BankAccount> = EventEnvelope {
+            aggregate_id: account_id.clone(),
+            sequence: 1,
+            payload: BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(12_500),
+                balance: Money::from_cents(12_500),
+            },
+            metadata,
+        };
+
+        client
+            .append_to_stream(
+                stream_name(&account_id),
+                &Default::default(),
+                to_event_data(&event).unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let events = read_events(&client, &account_id).await.unwrap();
+        assert_eq!(events.len(), 1);
+        let read = &events[0];
+        assert_eq!(read.aggregate_id, account_id);
+        assert_eq!(read.sequence, 1);
+        assert_eq!(read.payload, event.payload);
+        assert_eq!(read.metadata.get("correlation_id").unwrap(), "corr-8812");
+        assert_eq!(read.metadata.get("source_service").unwrap(), "cqrs-demo");
+        assert_eq!(read.metadata.get("schema_version").unwrap(), "1.0");
+    }
+
+    #[tokio::test]
+    async fn test_stream_events() {
+        let client = get_client().unwrap();
+        let account_id = format!("stream-test-{}", chrono::Utc::now().timestamp_nanos());
+        for sequence in 1..=3 {
+            let event: EventEnvelope<BankAccount> = EventEnvelope {
+                aggregate_id: account_id.clone(),
+                sequence,
+                payload: BankAccountEvent::CustomerDepositedMoney {
+                    amount: Money::from_cents(1_000),
+                    balance: Money::from_cents(1_000 * sequence as i64),
+                },
+                metadata: HashMap::new(),
+            };
+            client
+                .append_to_stream(
+                    stream_name(&account_id),
+                    &Default::default(),
+                    to_event_data(&event).unwrap(),
+                )
+                .await
+                .unwrap();
+        }
 
+        let count = stream_events(&client, &account_id)
+            .try_fold(0, |count, _| async move { Ok(count + 1) })
+            .await
+            .unwrap();
+        assert_eq!(count, 3);
+    }
 }
@@ -1,6 +1,15 @@
-use eventstore::{ Client, EventData };
+use std::error::Error;
+use std::time::Duration;
+
+use cqrs_es::{Aggregate, Query};
+use eventstore::{
+    Client, EventData, Position, StreamPosition, SubscribeToAllOptions, SubscriptionFilter,
+};
 use serde::{Serialize, Deserialize};
 
+use crate::domain::aggregate::BankAccount;
+use crate::eventstore_client::{get_client, to_envelope};
+
 
 #[derive(Serialize, Deserialize, Debug)]
 struct Foo {
@@ -9,9 +18,7 @@ struct Foo {
 
 #[tokio::main]
 pub async fn connect_event_store() -> Result<(), Box<dyn std::error::Error>>{
-    let settings = "esdb://127.0.0.1:2113?tls=false&keepAliveTimeout=10000&keepAliveInterval=10000".parse()?;
-
-    let client = Client::new(settings)?;
+    let client = get_client()?;
 
     let payload = Foo {
         is_event_store_a_good_db: true,
@@ -36,4 +43,174 @@ pub async fn connect_event_store() -> Result<(), Box<dyn std::error::Error>>{
 
     Ok(())
 
-}  
\ No newline at end of file
+}
+
+// How long to wait before resubscribing once the connection drops, doubling
+// with each attempt that fails.
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+// Keeps a query up to date with every account event in EventStore, e.g., to
+// drive a read model from a separate process. A catch-up subscription to `$all`,
+// filtered to the account streams, first replays the history and then follows
+// new events as they are appended. Each event is dispatched on its own.
+//
+// The position of the last event dispatched is tracked so that, when the
+// connection drops, the subscription is resumed just after it with an
+// increasing backoff rather than replaying everything. This only returns on an
+// error that retrying would not fix, e.g., an event that cannot be read.
+pub async fn run_subscription<Q: Query<BankAccount>>(
+    client: &Client,
+    processor: Q,
+) -> Result<(), Box<dyn Error>> {
+    let stream_prefix = format!("{}-", BankAccount::aggregate_type());
+    let filter = SubscriptionFilter::on_stream_name().add_prefix(&stream_prefix);
+    let mut last_position: Option<Position> = None;
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        let start = match last_position {
+            Some(position) => StreamPosition::Position(position),
+            None => StreamPosition::Start,
+        };
+        let options = SubscribeToAllOptions::default()
+            .position(start)
+            .filter(filter.clone());
+        let mut subscription = client.subscribe_to_all(&options).await;
+        loop {
+            let resolved = match subscription.next().await {
+                Ok(resolved) => resolved,
+                Err(err) if is_transient(&err) => {
+                    println!(
+                        "Error: subscription dropped after position {:?}, retrying in {:?}: {}\n",
+                        last_position, backoff, err
+                    );
+                    break;
+                }
+                Err(err) => return Err(err.into()),
+            };
+            let recorded = resolved.get_original_event();
+            let aggregate_id = recorded
+                .stream_id
+                .strip_prefix(&stream_prefix)
+                .unwrap_or(&recorded.stream_id)
+                .to_string();
+            let event = to_envelope(&aggregate_id, recorded)?;
+            processor.dispatch(&aggregate_id, &[event]).await;
+            last_position = Some(recorded.position);
+            backoff = INITIAL_BACKOFF;
+        }
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+// Whether a subscription failed because the server could not be reached for a
+// while, rather than because of anything that would happen again on a retry.
+fn is_transient(err: &eventstore::Error) -> bool {
+    matches!(
+        err,
+        eventstore::Error::ConnectionClosed
+            | eventstore::Error::GrpcConnectionError(_)
+            | eventstore::Error::DeadlineExceeded
+            | eventstore::Error::NotLeaderException(_)
+            | eventstore::Error::ServerError(_)
+    )
+}
+
+// These tests require a running EventStore instance (`docker-compose up -d`),
+// run them with `cargo test --features eventstore-integration`.
+#[cfg(all(test, feature = "eventstore-integration"))]
+mod eventstore_subscription_tests {
+    use async_trait::async_trait;
+    use chrono::{TimeZone, Utc};
+    use cqrs_es::{EventEnvelope, Query};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use crate::domain::aggregate::BankAccount;
+    use crate::domain::events::BankAccountEvent;
+    use crate::domain::money::Money;
+    use crate::eventstore_client::{get_client, to_event_data};
+    use crate::eventstore_connection::run_subscription;
+
+    // Records every event it is dispatched.
+    #[derive(Clone, Default)]
+    struct RecordingQuery {
+        events: Arc<Mutex<Vec<EventEnvelope<BankAccount>>>>,
+    }
+
+    #[async_trait]
+    impl Query<BankAccount> for RecordingQuery {
+        async fn dispatch(&self, _aggregate_id: &str, events: &[EventEnvelope<BankAccount>]) {
+            self.events.lock().unwrap().extend_from_slice(events);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscription_dispatches_appended_events() {
+        let client = get_client().unwrap();
+        let account_id = format!("subscription-test-{}", Utc::now().timestamp_nanos());
+        let stream_name = format!("account-{}", account_id);
+        let payloads = [
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(10_000),
+                balance: Money::from_cents(10_000),
+            },
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(2_500),
+                balance: Money::from_cents(7_500),
+                at: Utc.with_ymd_and_hms(2023, 3, 1, 9, 0, 0).unwrap(),
+            },
+        ];
+        for (index, payload) in payloads.iter().enumerate() {
+            let event: EventEnvelope<BankAccount> = EventEnvelope {
+                aggregate_id: account_id.clone(),
+                sequence: index + 1,
+                payload: payload.clone(),
+                metadata: HashMap::new(),
+            };
+            client
+                .append_to_stream(
+                    stream_name.as_str(),
+                    &Default::default(),
+                    to_event_data(&event).unwrap(),
+                )
+                .await
+                .unwrap();
+        }
+
+        let query = RecordingQuery::default();
+        let observed = || -> Vec<EventEnvelope<BankAccount>> {
+            query
+                .events
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|event| event.aggregate_id == account_id)
+                .cloned()
+                .collect()
+        };
+        let caught_up = async {
+            while observed().len() < payloads.len() {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(30), async {
+            tokio::select! {
+                result = run_subscription(&client, query.clone()) => {
+                    panic!("subscription ended: {:?}", result.err().map(|e| e.to_string()))
+                }
+                _ = caught_up => {}
+            }
+        })
+        .await
+        .expect("subscription did not observe the appended events");
+
+        let events = observed();
+        assert_eq!(events[0].sequence, 1);
+        assert_eq!(events[0].payload, payloads[0]);
+        assert_eq!(events[1].sequence, 2);
+        assert_eq!(events[1].payload, payloads[1]);
+    }
+}
@@ -0,0 +1,264 @@
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use cqrs_es::persist::ViewRepository;
+use cqrs_es::{AggregateError, CqrsFramework, EventStore};
+
+use crate::api_error::ApiError;
+use crate::book_transfer::book_transfer;
+use crate::command_extractor::CommandExtractor;
+use crate::domain::aggregate::BankAccount;
+use crate::domain::commands::BankAccountCommand;
+use crate::domain::errors::BankAccountError;
+use crate::queries::BankAccountView;
+
+// The state shared by the handlers of the HTTP API: the framework that commands
+// are dispatched through and the repository holding the `BankAccountView`s.
+pub struct HttpState<ES: EventStore<BankAccount>, V> {
+    pub cqrs: Arc<CqrsFramework<BankAccount, ES>>,
+    pub account_query: Arc<V>,
+}
+
+impl<ES: EventStore<BankAccount>, V> Clone for HttpState<ES, V> {
+    fn clone(&self) -> Self {
+        Self {
+            cqrs: self.cqrs.clone(),
+            account_query: self.account_query.clone(),
+        }
+    }
+}
+
+// Builds the HTTP API over any event store and view repository, so that the same
+// routes serve the Postgres-backed application and in-memory tests:
+//
+// - `POST /accounts/:account_id/commands` executes a JSON `BankAccountCommand`
+// - `GET /accounts/:account_id` responds with the account's `BankAccountView`
+pub fn router<ES, V>(cqrs: Arc<CqrsFramework<BankAccount, ES>>, account_query: Arc<V>) -> Router
+where
+    ES: EventStore<BankAccount> + 'static,
+    ES::AC: Send,
+    V: ViewRepository<BankAccountView, BankAccount> + 'static,
+{
+    Router::new()
+        .route("/accounts/:account_id", get(view_handler::<ES, V>))
+        .route(
+            "/accounts/:account_id/commands",
+            post(command_handler::<ES, V>),
+        )
+        .with_state(HttpState {
+            cqrs,
+            account_query,
+        })
+}
+
+// Responds with the `BankAccountView` of the account, or 404 if the account is
+// unknown.
+async fn view_handler<ES, V>(
+    Path(account_id): Path<String>,
+    State(state): State<HttpState<ES, V>>,
+) -> Response
+where
+    ES: EventStore<BankAccount>,
+    V: ViewRepository<BankAccountView, BankAccount>,
+{
+    match state.account_query.load(&account_id).await {
+        Ok(Some(view)) => (StatusCode::OK, Json(view)).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => {
+            println!("Error: {:#?}\n", err);
+            (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+        }
+    }
+}
+
+// Executes the command against the account, responding with 200 on success or
+// with an `ApiError` describing why the command was rejected. As with
+// `route_handler::command_handler`, a book transfer is coordinated by
+// `book_transfer` since it also changes the destination account, and the steps
+// it issues to that account are rejected when sent directly.
+async fn command_handler<ES, V>(
+    Path(account_id): Path<String>,
+    State(state): State<HttpState<ES, V>>,
+    CommandExtractor(metadata, command): CommandExtractor,
+) -> Response
+where
+    ES: EventStore<BankAccount>,
+    ES::AC: Send,
+    V: ViewRepository<BankAccountView, BankAccount>,
+{
+    if command.is_book_transfer_step() {
+        let err = AggregateError::UserError(BankAccountError::from("command not permitted"));
+        return ApiError::from(err).into_response();
+    }
+    let result = match command {
+        BankAccountCommand::BookTransfer {
+            to_account_id,
+            amount,
+            currency,
+        } => {
+            book_transfer(
+                &state.cqrs,
+                &account_id,
+                to_account_id,
+                amount,
+                currency,
+                metadata,
+            )
+            .await
+        }
+        command => {
+            state
+                .cqrs
+                .execute_with_metadata(&account_id, command, metadata)
+                .await
+        }
+    };
+    match result {
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(err) => {
+            println!("Error: {:#?}\n", err);
+            ApiError::from(err).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod http_tests {
+    use std::sync::Arc;
+
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use axum::Router;
+    use cqrs_es::mem_store::MemStore;
+    use cqrs_es::persist::GenericQuery;
+    use cqrs_es::CqrsFramework;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use crate::domain::aggregate::BankAccount;
+    use crate::http::router;
+    use crate::queries::{BankAccountView, InMemoryViewRepository};
+    use crate::services::{BankAccountServices, HappyPathBankAccountServices};
+
+    fn test_router() -> Router {
+        let repository = Arc::new(InMemoryViewRepository::default());
+        let account_query: GenericQuery<InMemoryViewRepository, BankAccountView, BankAccount> =
+            GenericQuery::new(repository.clone());
+        let services = BankAccountServices::new(Box::new(HappyPathBankAccountServices));
+        let cqrs = CqrsFramework::new(
+            MemStore::<BankAccount>::default(),
+            vec![Box::new(account_query)],
+            services,
+        );
+        router(Arc::new(cqrs), repository)
+    }
+
+    fn command(account_id: &str, body: &str) -> Request<Body> {
+        Request::post(format!("/accounts/{}/commands", account_id))
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    fn get_view(account_id: &str) -> Request<Body> {
+        Request::get(format!("/accounts/{}", account_id))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    async fn json_body(response: axum::response::Response) -> Value {
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_deposit_and_get_view() {
+        let router = test_router();
+        let response = router
+            .clone()
+            .oneshot(command(
+                "ACCT-1",
+                r#"{"OpenAccount": {"account_id": "ACCT-1", "currency": "USD", "holder_name": "Jane Doe"}}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = router
+            .clone()
+            .oneshot(command(
+                "ACCT-1",
+                r#"{"DepositMoney": {"amount": "250.00", "currency": "USD"}}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = router.oneshot(get_view("ACCT-1")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let view = json_body(response).await;
+        assert_eq!(view["account_id"], "ACCT-1");
+        assert_eq!(view["balance"], "250.00");
+        assert_eq!(view["currency"], "USD");
+    }
+
+    #[tokio::test]
+    async fn test_domain_error_is_bad_request() {
+        let router = test_router();
+        router
+            .clone()
+            .oneshot(command(
+                "ACCT-1",
+                r#"{"OpenAccount": {"account_id": "ACCT-1", "currency": "USD", "holder_name": "Jane Doe"}}"#,
+            ))
+            .await
+            .unwrap();
+        let response = router
+            .oneshot(command(
+                "ACCT-1",
+                r#"{"WithdrawMoney": {"amount": "100.00", "atm_id": "ATM-1", "authorized_by": null, "currency": "USD"}}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(json_body(response).await["message"], "funds not available");
+    }
+
+    #[tokio::test]
+    async fn test_book_transfer_step_is_rejected() {
+        let router = test_router();
+        router
+            .clone()
+            .oneshot(command(
+                "ACCT-1",
+                r#"{"OpenAccount": {"account_id": "ACCT-1", "currency": "USD", "holder_name": "Jane Doe"}}"#,
+            ))
+            .await
+            .unwrap();
+        let response = router
+            .clone()
+            .oneshot(command(
+                "ACCT-1",
+                r#"{"ReceiveBookTransfer": {"from_account_id": "ACCT-2", "amount": "100.00", "currency": "USD"}}"#,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            json_body(response).await["message"],
+            "command not permitted"
+        );
+
+        let response = router.oneshot(get_view("ACCT-1")).await.unwrap();
+        assert_eq!(json_body(response).await["balance"], "0.00");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_account_is_not_found() {
+        let response = test_router().oneshot(get_view("ACCT-404")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
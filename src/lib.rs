@@ -1,12 +1,18 @@
 #![forbid(unsafe_code)]
 #![deny(clippy::all)]
 
+mod api_error;
+mod book_transfer;
+pub mod cli;
 pub mod command_extractor;
+pub mod currency_backfill;
 mod config;
 mod domain;
-mod queries;
+pub mod http;
+pub mod queries;
 pub mod route_handler;
 mod services;
 pub mod state;
 pub mod eventstore_connection;
 pub mod eventstore_client;
+pub mod persistence;
@@ -1,6 +1,7 @@
 use axum::routing::get;
 use axum::Router;
-use cqrs_demo::route_handler::{command_handler, query_handler};
+use cqrs_demo::http::router;
+use cqrs_demo::route_handler::{balance_handler, command_handler, query_handler};
 use cqrs_demo::state::new_application_state;
 
 #[tokio::main]
@@ -14,7 +15,11 @@ async fn main() {
             "/account/:account_id",
             get(query_handler).post(command_handler),
         )
-        .with_state(state);
+        .route("/account/:account_id/balance", get(balance_handler))
+        .with_state(state.clone())
+        // The same account is also served as `/accounts/:account_id`, with its
+        // commands posted to `/accounts/:account_id/commands`.
+        .merge(router(state.cqrs, state.account_query));
     // Start the Axum server.
     axum::Server::bind(&"0.0.0.0:3030".parse().unwrap())
         .serve(router.into_make_service())
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use async_trait::async_trait;
+use cqrs_es::{Aggregate, AggregateContext, AggregateError, EventEnvelope, EventStore};
+use eventstore::{
+    AppendToStreamOptions, Client, ExpectedRevision, ReadStreamOptions, StreamPosition,
+};
+
+use crate::domain::upcast::Upcast;
+use crate::eventstore_client::{to_envelope, to_event_data};
+
+// An event store for the cqrs-es framework backed by EventStoreDB. Each
+// aggregate instance is held in its own stream, named for the aggregate type and
+// id, e.g., `account-ACCT-1`. Commits use the sequence of the last loaded event
+// as the expected revision of the stream, so a concurrent commit to the same
+// aggregate fails with `AggregateError::AggregateConflict`.
+pub struct EventStoreDbStore<A: Aggregate> {
+    client: Client,
+    _phantom: PhantomData<A>,
+}
+
+// Clones share the connection to EventStore.
+impl<A: Aggregate> Clone for EventStoreDbStore<A> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<A: Aggregate> EventStoreDbStore<A>
+where
+    A::Event: Upcast,
+{
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            _phantom: PhantomData,
+        }
+    }
+
+    fn stream_name(aggregate_id: &str) -> String {
+        format!("{}-{}", A::aggregate_type(), aggregate_id)
+    }
+
+    // Reads the events following `after_sequence` in the aggregate's stream.
+    async fn load_events_after(
+        &self,
+        aggregate_id: &str,
+        after_sequence: usize,
+    ) -> Result<Vec<EventEnvelope<A>>, AggregateError<A::Error>> {
+        let stream_name = Self::stream_name(aggregate_id);
+        // EventStore revisions start at zero, aggregate sequences start at one.
+        let options =
+            ReadStreamOptions::default().position(StreamPosition::Position(after_sequence as u64));
+        let mut reader = self
+            .client
+            .read_stream(stream_name.as_str(), &options)
+            .await
+            .map_err(connection_error)?;
+        let mut events = Vec::new();
+        loop {
+            match reader.next().await {
+                Ok(Some(resolved)) => {
+                    events.push(to_envelope(aggregate_id, resolved.get_original_event())?);
+                }
+                // A stream that does not exist yet belongs to a new aggregate.
+                Ok(None) | Err(eventstore::Error::ResourceNotFound) => return Ok(events),
+                Err(err) => return Err(connection_error(err)),
+            }
+        }
+    }
+
+    // Brings the aggregate in `context` up to date by applying the events
+    // committed after it.
+    pub(crate) async fn load_remaining(
+        &self,
+        mut context: EventStoreDbAggregateContext<A>,
+    ) -> Result<EventStoreDbAggregateContext<A>, AggregateError<A::Error>> {
+        let events = self
+            .load_events_after(&context.aggregate_id, context.current_sequence)
+            .await?;
+        for event in events {
+            context.apply(event);
+        }
+        Ok(context)
+    }
+}
+
+pub struct EventStoreDbAggregateContext<A: Aggregate> {
+    aggregate_id: String,
+    aggregate: A,
+    current_sequence: usize,
+}
+
+impl<A: Aggregate> EventStoreDbAggregateContext<A> {
+    // The aggregate as of `current_sequence`, e.g., restored from a snapshot.
+    pub(crate) fn new(aggregate_id: &str, aggregate: A, current_sequence: usize) -> Self {
+        Self {
+            aggregate_id: aggregate_id.to_string(),
+            aggregate,
+            current_sequence,
+        }
+    }
+
+    pub(crate) fn current_sequence(&self) -> usize {
+        self.current_sequence
+    }
+
+    pub(crate) fn apply(&mut self, event: EventEnvelope<A>) {
+        self.current_sequence = event.sequence;
+        self.aggregate.apply(event.payload);
+    }
+}
+
+impl<A: Aggregate> AggregateContext<A> for EventStoreDbAggregateContext<A> {
+    fn aggregate(&self) -> &A {
+        &self.aggregate
+    }
+}
+
+#[async_trait]
+impl<A: Aggregate> EventStore<A> for EventStoreDbStore<A>
+where
+    A::Event: Upcast,
+{
+    type AC = EventStoreDbAggregateContext<A>;
+
+    async fn load_events(
+        &self,
+        aggregate_id: &str,
+    ) -> Result<Vec<EventEnvelope<A>>, AggregateError<A::Error>> {
+        self.load_events_after(aggregate_id, 0).await
+    }
+
+    async fn load_aggregate(
+        &self,
+        aggregate_id: &str,
+    ) -> Result<Self::AC, AggregateError<A::Error>> {
+        let context = EventStoreDbAggregateContext::new(aggregate_id, A::default(), 0);
+        self.load_remaining(context).await
+    }
+
+    async fn commit(
+        &self,
+        events: Vec<A::Event>,
+        context: Self::AC,
+        metadata: HashMap<String, String>,
+    ) -> Result<Vec<EventEnvelope<A>>, AggregateError<A::Error>> {
+        if events.is_empty() {
+            return Ok(Vec::new());
+        }
+        let aggregate_id = context.aggregate_id;
+        let envelopes: Vec<EventEnvelope<A>> = events
+            .into_iter()
+            .enumerate()
+            .map(|(index, payload)| EventEnvelope {
+                aggregate_id: aggregate_id.clone(),
+                sequence: context.current_sequence + index + 1,
+                payload,
+                metadata: metadata.clone(),
+            })
+            .collect();
+        let event_data = envelopes
+            .iter()
+            .map(to_event_data)
+            .collect::<serde_json::Result<Vec<_>>>()?;
+
+        // EventStore revisions start at zero, aggregate sequences start at one.
+        let expected_revision = match context.current_sequence {
+            0 => ExpectedRevision::NoStream,
+            sequence => ExpectedRevision::Exact(sequence as u64 - 1),
+        };
+        let options = AppendToStreamOptions::default().expected_revision(expected_revision);
+        match self
+            .client
+            .append_to_stream(Self::stream_name(&aggregate_id), &options, event_data)
+            .await
+        {
+            Ok(_) => Ok(envelopes),
+            Err(eventstore::Error::WrongExpectedVersion { .. }) => {
+                Err(AggregateError::AggregateConflict)
+            }
+            Err(err) => Err(connection_error(err)),
+        }
+    }
+}
+
+pub(crate) fn connection_error<T: std::error::Error>(err: eventstore::Error) -> AggregateError<T> {
+    AggregateError::DatabaseConnectionError(Box::new(err))
+}
+
+// These tests require a running EventStore instance (`docker-compose up -d`),
+// run them with `cargo test --features eventstore-integration`.
+#[cfg(all(test, feature = "eventstore-integration"))]
+mod eventstore_store_tests {
+    use cqrs_es::{AggregateContext, CqrsFramework, EventStore};
+
+    use crate::domain::aggregate::BankAccount;
+    use crate::domain::commands::BankAccountCommand;
+    use crate::domain::money::Money;
+    use crate::eventstore_client::get_client;
+    use crate::persistence::eventstore_store::EventStoreDbStore;
+    use crate::services::{BankAccountServices, HappyPathBankAccountServices};
+
+    fn store() -> EventStoreDbStore<BankAccount> {
+        EventStoreDbStore::new(get_client().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_open_deposit_and_reload() {
+        let account_id = format!("store-test-{}", chrono::Utc::now().timestamp_nanos());
+        let services = BankAccountServices::new(Box::new(HappyPathBankAccountServices));
+        let cqrs = CqrsFramework::new(store(), vec![], services);
+        let open = BankAccountCommand::OpenAccount {
+            account_id: account_id.clone(),
+            currency: "USD".to_string(),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
+        };
+        cqrs.execute(&account_id, open).await.unwrap();
+        let deposit = BankAccountCommand::DepositMoney {
+            amount: Money::from_cents(20_000),
+            currency: "USD".to_string(),
+        };
+        cqrs.execute(&account_id, deposit).await.unwrap();
+
+        let events = store().load_events(&account_id).await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].sequence, 2);
+        assert_eq!(events[1].payload.balance(), Some(Money::from_cents(20_000)));
+        let context = store().load_aggregate(&account_id).await.unwrap();
+        assert_eq!(
+            context.aggregate().summary(),
+            format!("account {}: balance 200.00 USD, 0 checks, open", account_id)
+        );
+    }
+}
@@ -0,0 +1,3 @@
+pub mod eventstore_store;
+pub mod snapshot;
+pub mod postgres_views;
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use cqrs_es::{Aggregate, AggregateContext, AggregateError, EventEnvelope, EventStore};
+use eventstore::{Client, EventData, ReadStreamOptions, StreamPosition};
+use serde::{Deserialize, Serialize};
+
+use crate::domain::aggregate::BankAccount;
+use crate::domain::errors::BankAccountError;
+use crate::domain::events::BankAccountEvent;
+use crate::persistence::eventstore_store::{
+    connection_error, EventStoreDbAggregateContext, EventStoreDbStore,
+};
+
+// Take a snapshot of an account every 100 events unless configured otherwise.
+const DEFAULT_SNAPSHOT_INTERVAL: usize = 100;
+
+// The state of an account at some point in its history, from which it can be
+// restored without replaying the events that led to it. The whole aggregate is
+// captured rather than just its balance so that a restored account enforces the
+// same rules as a replayed one, e.g., funds held for pending deposits.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BankAccountSnapshot {
+    account: BankAccount,
+}
+
+impl BankAccount {
+    pub fn snapshot(&self) -> BankAccountSnapshot {
+        BankAccountSnapshot {
+            account: self.clone(),
+        }
+    }
+
+    // Restores an account from a snapshot taken after the event at `version`,
+    // ready for the events that follow it to be applied.
+    pub fn from_snapshot(
+        aggregate_id: &str,
+        snapshot: BankAccountSnapshot,
+        version: usize,
+    ) -> EventStoreDbAggregateContext<BankAccount> {
+        EventStoreDbAggregateContext::new(aggregate_id, snapshot.account, version)
+    }
+}
+
+// A snapshot as written to EventStore, along with the version it was taken at.
+#[derive(Serialize, Deserialize)]
+struct StoredSnapshot {
+    version: usize,
+    snapshot: BankAccountSnapshot,
+}
+
+// Reads a snapshot written to EventStore. A snapshot taken before a field was
+// added to the account no longer deserializes, and defaulting the field could
+// restore an account in a state its events never led to, so such a snapshot is
+// treated as absent and the account is replayed in full.
+fn parse_snapshot(aggregate_id: &str, data: &[u8]) -> Option<StoredSnapshot> {
+    match serde_json::from_slice(data) {
+        Ok(stored) => Some(stored),
+        Err(err) => {
+            println!(
+                "Error: snapshot of {} could not be read, replaying its events: {}\n",
+                aggregate_id, err
+            );
+            None
+        }
+    }
+}
+
+// Wraps an `EventStoreDbStore` so that loading an account starts from its most
+// recent snapshot and replays only the events committed since. Snapshots are
+// kept in a stream of their own beside the account's events, e.g.,
+// `snapshot-account-ACCT-1`, and a new one is written whenever a commit takes
+// the account past a multiple of the snapshot interval. Snapshots are only an
+// optimization, an account without one is loaded by replaying its whole stream.
+pub struct SnapshottingEventStoreDbStore {
+    store: EventStoreDbStore<BankAccount>,
+    client: Client,
+    snapshot_interval: usize,
+}
+
+impl SnapshottingEventStoreDbStore {
+    pub fn new(client: Client) -> Self {
+        Self {
+            store: EventStoreDbStore::new(client.clone()),
+            client,
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+        }
+    }
+
+    pub fn with_snapshot_interval(mut self, snapshot_interval: usize) -> Self {
+        self.snapshot_interval = snapshot_interval.max(1);
+        self
+    }
+
+    fn snapshot_stream_name(aggregate_id: &str) -> String {
+        format!(
+            "snapshot-{}-{}",
+            BankAccount::aggregate_type(),
+            aggregate_id
+        )
+    }
+
+    async fn load_snapshot(
+        &self,
+        aggregate_id: &str,
+    ) -> Result<Option<StoredSnapshot>, AggregateError<BankAccountError>> {
+        let options = ReadStreamOptions::default()
+            .position(StreamPosition::End)
+            .backwards()
+            .max_count(1);
+        let mut reader = self
+            .client
+            .read_stream(Self::snapshot_stream_name(aggregate_id), &options)
+            .await
+            .map_err(connection_error)?;
+        match reader.next().await {
+            Ok(Some(resolved)) => Ok(parse_snapshot(
+                aggregate_id,
+                &resolved.get_original_event().data,
+            )),
+            Ok(None) | Err(eventstore::Error::ResourceNotFound) => Ok(None),
+            Err(err) => Err(connection_error(err)),
+        }
+    }
+
+    async fn save_snapshot(
+        &self,
+        aggregate_id: &str,
+        stored: &StoredSnapshot,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let event_data = EventData::json("BankAccountSnapshot", stored)?;
+        self.client
+            .append_to_stream(
+                Self::snapshot_stream_name(aggregate_id),
+                &Default::default(),
+                event_data,
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventStore<BankAccount> for SnapshottingEventStoreDbStore {
+    type AC = EventStoreDbAggregateContext<BankAccount>;
+
+    async fn load_events(
+        &self,
+        aggregate_id: &str,
+    ) -> Result<Vec<EventEnvelope<BankAccount>>, AggregateError<BankAccountError>> {
+        self.store.load_events(aggregate_id).await
+    }
+
+    async fn load_aggregate(
+        &self,
+        aggregate_id: &str,
+    ) -> Result<Self::AC, AggregateError<BankAccountError>> {
+        let context = match self.load_snapshot(aggregate_id).await? {
+            Some(stored) => {
+                BankAccount::from_snapshot(aggregate_id, stored.snapshot, stored.version)
+            }
+            None => EventStoreDbAggregateContext::new(aggregate_id, BankAccount::default(), 0),
+        };
+        self.store.load_remaining(context).await
+    }
+
+    async fn commit(
+        &self,
+        events: Vec<BankAccountEvent>,
+        context: Self::AC,
+        metadata: HashMap<String, String>,
+    ) -> Result<Vec<EventEnvelope<BankAccount>>, AggregateError<BankAccountError>> {
+        let previous_version = context.current_sequence();
+        let mut account = context.aggregate().clone();
+        let committed = self.store.commit(events, context, metadata).await?;
+        let version = committed
+            .last()
+            .map_or(previous_version, |event| event.sequence);
+        if version / self.snapshot_interval > previous_version / self.snapshot_interval {
+            let aggregate_id = committed[0].aggregate_id.clone();
+            for event in &committed {
+                account.apply(event.payload.clone());
+            }
+            let stored = StoredSnapshot {
+                version,
+                snapshot: account.snapshot(),
+            };
+            // The events are already committed, a missing snapshot only means
+            // that the next load replays more of them.
+            if let Err(err) = self.save_snapshot(&aggregate_id, &stored).await {
+                println!(
+                    "Error: snapshot of {} at {} could not be saved: {}\n",
+                    aggregate_id, version, err
+                );
+            }
+        }
+        Ok(committed)
+    }
+}
+
+#[cfg(test)]
+mod snapshot_tests {
+    use chrono::{TimeZone, Utc};
+    use cqrs_es::{Aggregate, AggregateContext, EventEnvelope};
+    use std::collections::HashMap;
+
+    use crate::domain::aggregate::BankAccount;
+    use crate::domain::events::BankAccountEvent;
+    use crate::domain::money::Money;
+    use crate::persistence::snapshot::{parse_snapshot, StoredSnapshot};
+
+    fn envelope(sequence: usize, payload: BankAccountEvent) -> EventEnvelope<BankAccount> {
+        EventEnvelope {
+            aggregate_id: "ACCT-1".to_string(),
+            sequence,
+            payload,
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_and_trailing_events_match_full_replay() {
+        let events = vec![
+            BankAccountEvent::AccountOpened {
+                account_id: "ACCT-1".to_string(),
+                currency: Some("USD".to_string()),
+                holder_name: "Jane Doe".to_string(),
+                email: None,
+            },
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(50_000),
+                balance: Money::from_cents(50_000),
+            },
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(12_000),
+                balance: Money::from_cents(38_000),
+                at: Utc.with_ymd_and_hms(2023, 3, 1, 9, 0, 0).unwrap(),
+            },
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(2_500),
+                balance: Money::from_cents(40_500),
+            },
+        ];
+        let mut replayed = BankAccount::default();
+        for event in events.clone() {
+            replayed.apply(event);
+        }
+
+        let mut snapshotted = BankAccount::default();
+        for event in events[..2].iter().cloned() {
+            snapshotted.apply(event);
+        }
+        let mut context = BankAccount::from_snapshot("ACCT-1", snapshotted.snapshot(), 2);
+        context.apply(envelope(3, events[2].clone()));
+        context.apply(envelope(4, events[3].clone()));
+
+        assert_eq!(context.current_sequence(), 4);
+        assert_eq!(context.aggregate().summary(), replayed.summary());
+        assert_eq!(
+            context.aggregate().summary(),
+            "account ACCT-1: balance 405.00 USD, 0 checks, open"
+        );
+    }
+
+    #[test]
+    fn test_parse_snapshot() {
+        let mut account = BankAccount::default();
+        account.apply(BankAccountEvent::AccountOpened {
+            account_id: "ACCT-1".to_string(),
+            currency: Some("USD".to_string()),
+            holder_name: "Jane Doe".to_string(),
+            email: None,
+        });
+        let stored = StoredSnapshot {
+            version: 1,
+            snapshot: account.snapshot(),
+        };
+        let data = serde_json::to_vec(&stored).unwrap();
+
+        let parsed = parse_snapshot("ACCT-1", &data).unwrap();
+        assert_eq!(parsed.version, 1);
+        assert_eq!(
+            parsed.snapshot.account.summary(),
+            "account ACCT-1: balance 0.00 USD, 0 checks, open"
+        );
+    }
+
+    #[test]
+    fn test_older_snapshot_is_treated_as_absent() {
+        // A snapshot taken before the holder, email and later fields were recorded.
+        let data = br#"{
+            "version": 2,
+            "snapshot": {
+                "account": {
+                    "opened": true,
+                    "account_id": "ACCT-1",
+                    "balance": "500.00",
+                    "checks_written": 0,
+                    "closed": false,
+                    "currency": "USD"
+                }
+            }
+        }"#;
+
+        assert!(parse_snapshot("ACCT-1", data).is_none());
+    }
+}
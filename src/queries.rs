@@ -1,14 +1,23 @@
 use async_trait::async_trait;
-use cqrs_es::persist::GenericQuery;
-use cqrs_es::{EventEnvelope, Query, View};
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
+use cqrs_es::persist::{
+    GenericQuery, PersistenceError, QueryErrorHandler, ViewContext, ViewRepository,
+};
+use cqrs_es::{AggregateError, EventEnvelope, EventStore, Query, View};
 use postgres_es::PostgresViewRepository;
-use eventstore::EventData;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
 
-use crate::domain::aggregate::BankAccount;
+use crate::domain::aggregate::{BankAccount, ACCOUNT_CURRENCY};
+use crate::domain::check_number::CheckNumber;
+use crate::domain::errors::BankAccountError;
 use crate::domain::events::BankAccountEvent;
-use crate::eventstore_client::get_client;
+use crate::domain::money::Money;
+use crate::eventstore_client::{get_client, to_event_data};
 
 pub struct SimpleLoggingQuery {}
 
@@ -17,40 +26,401 @@ pub struct SimpleLoggingQuery {}
 #[async_trait]
 impl Query<BankAccount> for SimpleLoggingQuery {
     async fn dispatch(&self, aggregate_id: &str, events: &[EventEnvelope<BankAccount>]) {
-        
         let client = get_client().unwrap();
 
-        
         for event in events {
             let payload = serde_json::to_string_pretty(&event.payload).unwrap();
-            let event_type: String;
+            println!("{}-{}\n{}", aggregate_id, event.sequence, payload);
 
-            let value: Value = serde_json::from_str(&payload).expect("Failed to parse JSON");
+            // Define the event along with its metadata
+            let evt = to_event_data(event).unwrap();
 
-            if let Value::Object(obj) = value {
-                let keys: Vec<String> = obj.keys().map(|key| key.to_string()).collect();
-                
-                if let Some(first_key) = keys.get(0) {
-                    event_type = first_key.clone();
-                } else {
-                    event_type = "Unknown".to_string();
+            // Define the Stream
+            client
+                .append_to_stream(aggregate_id.to_string(), &Default::default(), evt)
+                .await
+                .unwrap();
+        }
+    }
+}
+
+// Delivers spend alerts to the customer, e.g., by push notification or webhook.
+#[async_trait]
+pub trait SpendAlertNotifier: Sync + Send {
+    async fn notify(&self, account_id: &str, amount: Money, threshold: Money);
+}
+
+pub struct LoggingSpendAlertNotifier;
+
+#[async_trait]
+impl SpendAlertNotifier for LoggingSpendAlertNotifier {
+    async fn notify(&self, account_id: &str, amount: Money, threshold: Money) {
+        println!(
+            "spend alert for {}: debit of {} exceeds {}",
+            account_id, amount, threshold
+        );
+    }
+}
+
+// Notifies the customer for every spend alert the aggregate records.
+pub struct SpendAlertQuery {
+    notifier: Box<dyn SpendAlertNotifier>,
+}
+
+impl SpendAlertQuery {
+    pub fn new(notifier: Box<dyn SpendAlertNotifier>) -> Self {
+        Self { notifier }
+    }
+}
+
+#[async_trait]
+impl Query<BankAccount> for SpendAlertQuery {
+    async fn dispatch(&self, aggregate_id: &str, events: &[EventEnvelope<BankAccount>]) {
+        for event in events {
+            if let BankAccountEvent::SpendAlertTriggered { amount, threshold } = &event.payload {
+                self.notifier
+                    .notify(aggregate_id, *amount, *threshold)
+                    .await;
+            }
+        }
+    }
+}
+
+// A single transaction over the compliance threshold.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alert {
+    pub account_id: String,
+    pub sequence: usize,
+    pub amount: Money,
+    pub kind: String,
+}
+
+// Reports large transactions to compliance, e.g., by filing a case for review.
+#[async_trait]
+pub trait ComplianceAlertNotifier: Sync + Send {
+    async fn notify(&self, alert: &Alert);
+}
+
+pub struct LoggingComplianceAlertNotifier;
+
+#[async_trait]
+impl ComplianceAlertNotifier for LoggingComplianceAlertNotifier {
+    async fn notify(&self, alert: &Alert) {
+        println!(
+            "compliance alert for {}-{}: {} of {}",
+            alert.account_id, alert.sequence, alert.kind, alert.amount
+        );
+    }
+}
+
+// Reports every deposit, withdrawal or check larger than the threshold.
+pub struct AlertingQuery {
+    threshold: Money,
+    notifier: Box<dyn ComplianceAlertNotifier>,
+}
+
+impl AlertingQuery {
+    pub fn new(threshold: Money, notifier: Box<dyn ComplianceAlertNotifier>) -> Self {
+        Self {
+            threshold,
+            notifier,
+        }
+    }
+}
+
+#[async_trait]
+impl Query<BankAccount> for AlertingQuery {
+    async fn dispatch(&self, aggregate_id: &str, events: &[EventEnvelope<BankAccount>]) {
+        for event in events {
+            let (kind, amount) = match &event.payload {
+                BankAccountEvent::CustomerDepositedMoney { amount, .. } => ("deposit", amount),
+                BankAccountEvent::CheckImageDeposited { amount, .. } => ("check deposit", amount),
+                BankAccountEvent::CustomerWithdrewCash { amount, .. }
+                | BankAccountEvent::AtmWithdrawal { amount, .. } => ("withdrawal", amount),
+                BankAccountEvent::CustomerWroteCheck { amount, .. } => ("check", amount),
+                _ => continue,
+            };
+            if *amount > self.threshold {
+                let alert = Alert {
+                    account_id: aggregate_id.to_string(),
+                    sequence: event.sequence,
+                    amount: *amount,
+                    kind: kind.to_string(),
+                };
+                self.notifier.notify(&alert).await;
+            }
+        }
+    }
+}
+
+// Operational counts of the money moved through all accounts, see
+// `MetricsQueryProcessor`. Checks are counted on their own but, like cash
+// withdrawals, add to `total_withdrawn`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Metrics {
+    pub deposits: usize,
+    pub withdrawals: usize,
+    pub checks: usize,
+    pub total_deposited: Money,
+    pub total_withdrawn: Money,
+}
+
+// Counts the deposits, withdrawals and checks committed since the application
+// started, e.g., to be scraped by a monitoring system. Any other event, including
+// a check being voided, leaves the counts as they are.
+#[derive(Default)]
+pub struct MetricsQueryProcessor {
+    metrics: Mutex<Metrics>,
+}
+
+impl MetricsQueryProcessor {
+    pub fn snapshot(&self) -> Metrics {
+        self.metrics.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Query<BankAccount> for MetricsQueryProcessor {
+    async fn dispatch(&self, _aggregate_id: &str, events: &[EventEnvelope<BankAccount>]) {
+        let mut metrics = self.metrics.lock().unwrap();
+        for event in events {
+            match &event.payload {
+                BankAccountEvent::CustomerDepositedMoney { amount, .. }
+                | BankAccountEvent::CheckImageDeposited { amount, .. } => {
+                    metrics.deposits += 1;
+                    metrics.total_deposited += *amount;
+                }
+                BankAccountEvent::CustomerWithdrewCash { amount, .. }
+                | BankAccountEvent::AtmWithdrawal { amount, .. } => {
+                    metrics.withdrawals += 1;
+                    metrics.total_withdrawn += *amount;
                 }
-            } else {
-                event_type = "Uknown".to_string();
+                BankAccountEvent::CustomerWroteCheck { amount, .. } => {
+                    metrics.checks += 1;
+                    metrics.total_withdrawn += *amount;
+                }
+                _ => {}
             }
+        }
+    }
+}
 
-            println!("{}-{}\n{}", aggregate_id, event.sequence, payload);
-           
-            // Definte the Event Type
-            let evt = EventData::json(&event_type.to_string(), &payload).unwrap();
+// Forwards every committed event to subscribers within the application, e.g.,
+// to push account activity to connected clients as it happens. Events are only
+// delivered to receivers subscribed at the time, and a receiver that falls more
+// than `capacity` events behind misses the oldest of them (see `next_event`).
+// The broadcaster is cheap to clone, so one clone can be registered with the
+// framework while another is kept to subscribe from.
+#[derive(Clone)]
+pub struct EventBroadcaster {
+    sender: broadcast::Sender<EventEnvelope<BankAccount>>,
+}
 
-            // Define the Stream
-            client
-            .append_to_stream(aggregate_id.to_string(), &Default::default(), evt)
-            .await
-            .unwrap();
+impl EventBroadcaster {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<EventEnvelope<BankAccount>> {
+        self.sender.subscribe()
+    }
+}
+
+#[async_trait]
+impl Query<BankAccount> for EventBroadcaster {
+    async fn dispatch(&self, _aggregate_id: &str, events: &[EventEnvelope<BankAccount>]) {
+        for event in events {
+            // Sending only fails when nobody is subscribed, which is not an error.
+            let _ = self.sender.send(event.clone());
+        }
+    }
+}
+
+// The next event broadcast to `receiver`, or `None` once the broadcaster is
+// dropped. A receiver that has fallen behind skips the events it missed.
+pub async fn next_event(
+    receiver: &mut broadcast::Receiver<EventEnvelope<BankAccount>>,
+) -> Option<EventEnvelope<BankAccount>> {
+    loop {
+        match receiver.recv().await {
+            Ok(event) => return Some(event),
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                println!("Error: subscriber lagged, {} events were missed\n", missed);
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        }
+    }
+}
+
+// Wraps another query, dropping any event whose sequence has already been
+// dispatched for its aggregate (e.g., from a misconfigured replication delivering
+// an event twice) so that the wrapped query does not act on it again.
+// Sequences only ever increase, so anything at or below the highest sequence
+// seen for an aggregate is a duplicate. This is for queries without a view of
+// their own, e.g., those sending notifications; a view records the sequences it
+// has applied itself (see `AppliedSequence`). Only the `capacity` aggregates
+// dispatched most recently are remembered, so memory stays bounded at the cost
+// of a duplicate for a long-idle aggregate getting through.
+pub struct DeduplicatingQuery<Q: Query<BankAccount>> {
+    query: Q,
+    capacity: usize,
+    last_sequences: Mutex<LastSequences>,
+}
+
+#[derive(Default)]
+struct LastSequences {
+    sequences: HashMap<String, usize>,
+    // Aggregate ids, least recently dispatched first.
+    order: VecDeque<String>,
+}
+
+impl<Q: Query<BankAccount>> DeduplicatingQuery<Q> {
+    pub fn new(query: Q, capacity: usize) -> Self {
+        Self {
+            query,
+            capacity,
+            last_sequences: Mutex::new(LastSequences::default()),
+        }
+    }
+}
+
+#[async_trait]
+impl<Q: Query<BankAccount>> Query<BankAccount> for DeduplicatingQuery<Q> {
+    async fn dispatch(&self, aggregate_id: &str, events: &[EventEnvelope<BankAccount>]) {
+        let unique_events: Vec<EventEnvelope<BankAccount>> = {
+            let mut guard = self.last_sequences.lock().unwrap();
+            let LastSequences { sequences, order } = &mut *guard;
+            order.retain(|id| id != aggregate_id);
+            order.push_back(aggregate_id.to_string());
+            while order.len() > self.capacity {
+                if let Some(evicted) = order.pop_front() {
+                    sequences.remove(&evicted);
+                }
+            }
+            let last_sequence = sequences.entry(aggregate_id.to_string()).or_default();
+            events
+                .iter()
+                .filter(|event| {
+                    if event.sequence <= *last_sequence {
+                        println!(
+                            "dropping duplicate event {}-{}",
+                            aggregate_id, event.sequence
+                        );
+                        return false;
+                    }
+                    *last_sequence = event.sequence;
+                    true
+                })
+                .cloned()
+                .collect()
+        };
+        if !unique_events.is_empty() {
+            self.query.dispatch(aggregate_id, &unique_events).await;
+        }
+    }
+}
+
+// The sequence of the last event applied to a view, persisted along with it so
+// that an event dispatched again, even after a restart, is not applied twice.
+// A view stored before this was recorded starts from zero.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AppliedSequence(usize);
+
+impl AppliedSequence {
+    // Records `sequence` as applied, or returns false if it already was.
+    fn advance(&mut self, sequence: usize) -> bool {
+        if sequence <= self.0 {
+            return false;
+        }
+        self.0 = sequence;
+        true
+    }
+}
+
+// Holds the latest `BankAccountView` for each account in memory, for tests and
+// local development where a Postgres view repository is not available. Views
+// are not persisted and are lost on restart.
+#[derive(Default)]
+pub struct InMemoryViewRepository {
+    views: Mutex<HashMap<String, BankAccountView>>,
+}
+
+impl InMemoryViewRepository {
+    pub fn load_view(&self, account_id: &str) -> Option<BankAccountView> {
+        self.views.lock().unwrap().get(account_id).cloned()
+    }
+}
+
+#[async_trait]
+impl Query<BankAccount> for InMemoryViewRepository {
+    async fn dispatch(&self, aggregate_id: &str, events: &[EventEnvelope<BankAccount>]) {
+        let mut views = self.views.lock().unwrap();
+        let view = views.entry(aggregate_id.to_string()).or_default();
+        for event in events {
+            view.update(event);
+        }
+    }
+}
+
+// Also usable as the repository behind a `GenericQuery`, or wherever a view
+// repository is expected, e.g., the HTTP API. View versions are not tracked
+// since there is no concurrent writer to guard against.
+#[async_trait]
+impl ViewRepository<BankAccountView, BankAccount> for InMemoryViewRepository {
+    async fn load(&self, view_id: &str) -> Result<Option<BankAccountView>, PersistenceError> {
+        Ok(self.load_view(view_id))
+    }
+
+    async fn load_with_context(
+        &self,
+        view_id: &str,
+    ) -> Result<Option<(BankAccountView, ViewContext)>, PersistenceError> {
+        Ok(self
+            .load_view(view_id)
+            .map(|view| (view, ViewContext::new(view_id.to_string(), 0))))
+    }
+
+    async fn update_view(
+        &self,
+        view: BankAccountView,
+        context: ViewContext,
+    ) -> Result<(), PersistenceError> {
+        self.views
+            .lock()
+            .unwrap()
+            .insert(context.view_instance_id, view);
+        Ok(())
+    }
+}
+
+// Rebuilds a view of the account from its full history in the event store, e.g.,
+// to backfill a query added after the account was opened. An account without
+// any events has the default view.
+pub async fn rebuild_view<V: View<BankAccount>, S: EventStore<BankAccount>>(
+    store: &S,
+    account_id: &str,
+) -> Result<V, AggregateError<BankAccountError>> {
+    let mut view = V::default();
+    for event in store.load_events(account_id).await? {
+        view.update(&event);
+    }
+    Ok(view)
+}
+
+// Reports the sequence numbers missing from a loaded stream, e.g., where a failed
+// append left a hole, as inclusive ranges. Sequences start at one and the events
+// are expected in sequence order, as they are loaded.
+pub fn sequence_gaps(events: &[EventEnvelope<BankAccount>]) -> Vec<RangeInclusive<usize>> {
+    let mut gaps = Vec::new();
+    let mut expected = 1;
+    for event in events {
+        if event.sequence > expected {
+            gaps.push(expected..=event.sequence - 1);
         }
+        expected = expected.max(event.sequence + 1);
     }
+    gaps
 }
 
 // Our second query, this one will be handled with Postgres `GenericQuery`
@@ -64,22 +434,104 @@ pub type AccountQuery = GenericQuery<
 
 // The view for a BankAccount query, for a standard http application this should
 // be designed to reflect the response dto that will be returned to a user.
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct BankAccountView {
+    #[serde(default)]
+    applied: AppliedSequence,
     account_id: Option<String>,
-    balance: f64,
-    written_checks: Vec<String>,
+    #[serde(default)]
+    holder_name: String,
+    email: Option<String>,
+    balance: Money,
+    currency: Option<String>,
+    written_checks: Vec<CheckNumber>,
     issued_bonuses: Vec<String>,
+    #[serde(default)]
+    disbursed_loans: Vec<String>,
     ledger: Vec<LedgerEntry>,
+    #[serde(default)]
+    pending_deposits: Money,
+    last_updated: Option<DateTime<Utc>>,
+}
+
+impl BankAccountView {
+    pub fn account_id(&self) -> Option<&str> {
+        self.account_id.as_deref()
+    }
+
+    // Empty for accounts opened before the holder was recorded.
+    pub fn holder_name(&self) -> &str {
+        &self.holder_name
+    }
+
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    pub fn balance(&self) -> Money {
+        self.balance
+    }
+
+    // Accounts opened before their currency was recorded hold US dollars.
+    pub fn currency(&self) -> &str {
+        self.currency.as_deref().unwrap_or(ACCOUNT_CURRENCY)
+    }
+
+    pub fn written_checks(&self) -> &[CheckNumber] {
+        &self.written_checks
+    }
+
+    // The balance less any deposits still held pending clearance.
+    pub fn available_balance(&self) -> Money {
+        self.balance - self.pending_deposits
+    }
+
+    pub fn last_updated(&self) -> Option<DateTime<Utc>> {
+        self.last_updated
+    }
 }
 
+// The response body for a balance request, serialized as:
+//
+//     {
+//         "account_id": "test-acct-1234",
+//         "balance": "743.72",
+//         "available_balance": "743.72",
+//         "currency": "USD",
+//         "last_updated": "2023-03-01T09:00:00Z"
+//     }
+//
+// The available balance excludes mobile check deposits that have not yet
+// cleared. `last_updated` is null for an account that has never
+// received a command through the API.
 #[derive(Debug, Serialize, Deserialize)]
+pub struct BalanceResponse {
+    account_id: String,
+    balance: Money,
+    available_balance: Money,
+    currency: String,
+    last_updated: Option<DateTime<Utc>>,
+}
+
+impl From<&BankAccountView> for BalanceResponse {
+    fn from(view: &BankAccountView) -> Self {
+        Self {
+            account_id: view.account_id().unwrap_or_default().to_string(),
+            balance: view.balance(),
+            available_balance: view.available_balance(),
+            currency: view.currency().to_string(),
+            last_updated: view.last_updated(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LedgerEntry {
     description: String,
-    amount: f64,
+    amount: Money,
 }
 impl LedgerEntry {
-    fn new(description: &str, amount: f64) -> Self {
+    fn new(description: &str, amount: Money) -> Self {
         Self {
             description: description.to_string(),
             amount,
@@ -92,9 +544,27 @@ impl LedgerEntry {
 // design the events to carry the balance information instead.
 impl View<BankAccount> for BankAccountView {
     fn update(&mut self, event: &EventEnvelope<BankAccount>) {
+        if !self.applied.advance(event.sequence) {
+            return;
+        }
+        if let Some(time) = event_time(event) {
+            self.last_updated = Some(time);
+        }
         match &event.payload {
-            BankAccountEvent::AccountOpened { account_id } => {
+            BankAccountEvent::AccountOpened {
+                account_id,
+                currency,
+                holder_name,
+                email,
+            } => {
                 self.account_id = Some(account_id.clone());
+                self.currency = currency.clone();
+                self.holder_name = holder_name.clone();
+                self.email = email.clone();
+            }
+
+            BankAccountEvent::CurrencyBackfilled { currency } => {
+                self.currency = Some(currency.clone());
             }
 
             BankAccountEvent::CustomerDepositedMoney { amount, balance } => {
@@ -102,27 +572,2784 @@ impl View<BankAccount> for BankAccountView {
                 self.balance = *balance;
             }
 
-            BankAccountEvent::CustomerWithdrewCash { amount, balance } => {
+            BankAccountEvent::CustomerWithdrewCash {
+                amount, balance, ..
+            } => {
+                self.ledger
+                    .push(LedgerEntry::new("ATM_withdrawal", *amount));
+                self.balance = *balance;
+            }
+
+            BankAccountEvent::CheckImageDeposited {
+                amount, balance, ..
+            } => {
+                self.ledger.push(LedgerEntry::new("Check_deposit", *amount));
+                self.pending_deposits += *amount;
+                self.balance = *balance;
+            }
+
+            BankAccountEvent::CheckDepositCleared { amount, .. } => {
+                self.pending_deposits -= *amount;
+            }
+
+            BankAccountEvent::CheckDepositRejected {
+                amount, balance, ..
+            } => {
+                self.ledger.push(LedgerEntry::new("Check_return", *amount));
+                self.pending_deposits -= *amount;
+                self.balance = *balance;
+            }
+
+            BankAccountEvent::AtmWithdrawal {
+                amount, balance, ..
+            } => {
                 self.ledger
                     .push(LedgerEntry::new("ATM_withdrawal", *amount));
                 self.balance = *balance;
             }
 
+            BankAccountEvent::PurchaseWithCashback {
+                purchase_amount,
+                cashback_amount,
+                balance,
+                ..
+            } => {
+                self.ledger
+                    .push(LedgerEntry::new("Purchase", *purchase_amount));
+                self.ledger
+                    .push(LedgerEntry::new("Cashback", *cashback_amount));
+                self.balance = *balance;
+            }
+
             BankAccountEvent::CustomerWroteCheck {
                 check_number,
                 amount,
                 balance,
+                ..
             } => {
-                self.ledger.push(LedgerEntry::new(check_number, *amount));
+                self.ledger
+                    .push(LedgerEntry::new(check_number.as_str(), *amount));
                 self.written_checks.push(check_number.clone());
                 self.balance = *balance;
             }
 
-            BankAccountEvent::IssuedBonus { bonus_id, amount, balance } => {
+            BankAccountEvent::IssuedBonus {
+                bonus_id,
+                amount,
+                balance,
+            } => {
                 self.ledger.push(LedgerEntry::new("Bonus", *amount));
                 self.issued_bonuses.push(bonus_id.clone());
                 self.balance = *balance;
             }
+
+            BankAccountEvent::InterestAccrued { amount, balance } => {
+                self.ledger.push(LedgerEntry::new("Interest", *amount));
+                self.balance = *balance;
+            }
+
+            BankAccountEvent::CheckVoided {
+                check_number,
+                amount,
+                balance,
+            } => {
+                self.ledger.push(LedgerEntry::new("Check_void", *amount));
+                self.written_checks
+                    .retain(|written| written != check_number);
+                self.balance = *balance;
+            }
+
+            BankAccountEvent::OverdraftFeeCharged { fee, balance } => {
+                self.ledger.push(LedgerEntry::new("Overdraft_fee", *fee));
+                self.balance = *balance;
+            }
+
+            BankAccountEvent::AdjustmentProposed { .. } => {}
+
+            BankAccountEvent::AdjustmentApproved { delta, balance, .. } => {
+                self.ledger.push(LedgerEntry::new("Reconciliation", *delta));
+                self.balance = *balance;
+            }
+
+            BankAccountEvent::LoanDisbursed {
+                loan_id,
+                amount,
+                balance,
+            } => {
+                self.ledger.push(LedgerEntry::new("Loan", *amount));
+                self.disbursed_loans.push(loan_id.clone());
+                self.balance = *balance;
+            }
+
+            BankAccountEvent::WireSent {
+                amount,
+                fee,
+                balance,
+                ..
+            } => {
+                self.ledger.push(LedgerEntry::new("Wire", *amount));
+                self.ledger.push(LedgerEntry::new("Wire_fee", *fee));
+                self.balance = *balance;
+            }
+
+            BankAccountEvent::BookTransferSent {
+                amount, balance, ..
+            } => {
+                self.ledger.push(LedgerEntry::new("Book_transfer", *amount));
+                self.balance = *balance;
+            }
+
+            BankAccountEvent::BookTransferReceived {
+                amount, balance, ..
+            } => {
+                self.ledger.push(LedgerEntry::new("Book_transfer", *amount));
+                self.balance = *balance;
+            }
+
+            BankAccountEvent::BookTransferReversed {
+                amount, balance, ..
+            } => {
+                self.ledger
+                    .push(LedgerEntry::new("Book_transfer_reversal", *amount));
+                self.balance = *balance;
+            }
+
+            BankAccountEvent::DirectDebitReturned {
+                amount, balance, ..
+            } => {
+                self.ledger
+                    .push(LedgerEntry::new("Direct_debit_return", *amount));
+                self.balance = *balance;
+            }
+
+            BankAccountEvent::DirectDebitRetried {
+                amount, balance, ..
+            } => {
+                self.ledger.push(LedgerEntry::new("Direct_debit", *amount));
+                self.balance = *balance;
+            }
+
+            BankAccountEvent::ScheduledPaymentMade {
+                amount, balance, ..
+            } => {
+                self.ledger.push(LedgerEntry::new("Bill_payment", *amount));
+                self.balance = *balance;
+            }
+
+            BankAccountEvent::AccountNoteAdded { .. }
+            | BankAccountEvent::JointHolderAdded { .. }
+            | BankAccountEvent::PaperlessEnrolled
+            | BankAccountEvent::PaperlessDisenrolled
+            | BankAccountEvent::StatementDelivered { .. }
+            | BankAccountEvent::KycStatusRecorded { .. }
+            | BankAccountEvent::SpendAlertSet { .. }
+            | BankAccountEvent::SpendAlertTriggered { .. }
+            | BankAccountEvent::LowBalanceThresholdSet { .. }
+            | BankAccountEvent::LowBalanceWarningTriggered { .. }
+            | BankAccountEvent::PaymentScheduled { .. }
+            | BankAccountEvent::PaymentFailed { .. }
+            | BankAccountEvent::TransactionFlagged { .. }
+            | BankAccountEvent::FlagCleared { .. }
+            | BankAccountEvent::AccountClosed { .. }
+            | BankAccountEvent::OverdraftPolicySet { .. }
+            | BankAccountEvent::CommandProcessed { .. } => {}
         }
     }
 }
+
+// Commands submitted through the API carry the time they were received in their
+// metadata (see `CommandExtractor`), projections that need to know when an event
+// occurred read it from there.
+fn event_time(event: &EventEnvelope<BankAccount>) -> Option<DateTime<Utc>> {
+    let time = event.metadata.get("time")?;
+    DateTime::parse_from_rfc3339(time)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+// Tracks how long a new account takes to receive its first deposit,
+// used for onboarding funnel analytics.
+pub type ActivationQuery =
+    GenericQuery<PostgresViewRepository<ActivationView, BankAccount>, ActivationView, BankAccount>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ActivationView {
+    #[serde(default)]
+    applied: AppliedSequence,
+    opened_at: Option<DateTime<Utc>>,
+    first_deposit_at: Option<DateTime<Utc>>,
+}
+
+impl ActivationView {
+    // Returns `None` until the account has both been opened and received a deposit.
+    pub fn time_to_first_deposit(&self) -> Option<Duration> {
+        Some(self.first_deposit_at? - self.opened_at?)
+    }
+}
+
+impl View<BankAccount> for ActivationView {
+    fn update(&mut self, event: &EventEnvelope<BankAccount>) {
+        if !self.applied.advance(event.sequence) {
+            return;
+        }
+        match &event.payload {
+            BankAccountEvent::AccountOpened { .. } => {
+                self.opened_at = event_time(event);
+            }
+            BankAccountEvent::CustomerDepositedMoney { .. } if self.first_deposit_at.is_none() => {
+                self.first_deposit_at = event_time(event);
+            }
+            _ => {}
+        }
+    }
+}
+
+// Presents the account as an accountant's T-account, debits (money leaving the
+// account) on the left and credits (money entering the account) on the right.
+pub type TAccountQuery =
+    GenericQuery<PostgresViewRepository<TAccountView, BankAccount>, TAccountView, BankAccount>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TAccountView {
+    #[serde(default)]
+    applied: AppliedSequence,
+    debits: Vec<TAccountEntry>,
+    credits: Vec<TAccountEntry>,
+    total_debits: Money,
+    total_credits: Money,
+    balance: Money,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TAccountEntry {
+    description: String,
+    amount: Money,
+    running_total: Money,
+}
+
+impl TAccountView {
+    pub fn total_debits(&self) -> Money {
+        self.total_debits
+    }
+
+    pub fn total_credits(&self) -> Money {
+        self.total_credits
+    }
+
+    pub fn balance(&self) -> Money {
+        self.balance
+    }
+
+    fn debit(&mut self, description: &str, amount: Money) {
+        self.total_debits += amount;
+        self.debits.push(TAccountEntry {
+            description: description.to_string(),
+            amount,
+            running_total: self.total_debits,
+        });
+    }
+
+    fn credit(&mut self, description: &str, amount: Money) {
+        self.total_credits += amount;
+        self.credits.push(TAccountEntry {
+            description: description.to_string(),
+            amount,
+            running_total: self.total_credits,
+        });
+    }
+}
+
+// The side an amount is posted to is decided by the direction of the money, the
+// balance carried on each event is kept so the two columns can be reconciled.
+impl View<BankAccount> for TAccountView {
+    fn update(&mut self, event: &EventEnvelope<BankAccount>) {
+        if !self.applied.advance(event.sequence) {
+            return;
+        }
+        match &event.payload {
+            BankAccountEvent::AccountOpened { .. } => {}
+            BankAccountEvent::CustomerDepositedMoney { amount, balance } => {
+                self.credit("Deposit", *amount);
+                self.balance = *balance;
+            }
+            BankAccountEvent::CustomerWithdrewCash {
+                amount, balance, ..
+            } => {
+                self.debit("ATM_withdrawal", *amount);
+                self.balance = *balance;
+            }
+            BankAccountEvent::CheckImageDeposited {
+                amount, balance, ..
+            } => {
+                self.credit("Check_deposit", *amount);
+                self.balance = *balance;
+            }
+            BankAccountEvent::CheckDepositRejected {
+                amount, balance, ..
+            } => {
+                self.debit("Check_return", *amount);
+                self.balance = *balance;
+            }
+            BankAccountEvent::AtmWithdrawal {
+                amount, balance, ..
+            } => {
+                self.debit("ATM_withdrawal", *amount);
+                self.balance = *balance;
+            }
+            BankAccountEvent::PurchaseWithCashback {
+                purchase_amount,
+                cashback_amount,
+                balance,
+                ..
+            } => {
+                self.debit("Purchase", *purchase_amount);
+                self.debit("Cashback", *cashback_amount);
+                self.balance = *balance;
+            }
+            BankAccountEvent::CustomerWroteCheck {
+                check_number,
+                amount,
+                balance,
+                ..
+            } => {
+                self.debit(check_number.as_str(), *amount);
+                self.balance = *balance;
+            }
+            BankAccountEvent::IssuedBonus {
+                amount, balance, ..
+            } => {
+                self.credit("Bonus", *amount);
+                self.balance = *balance;
+            }
+            BankAccountEvent::InterestAccrued { amount, balance } => {
+                self.credit("Interest", *amount);
+                self.balance = *balance;
+            }
+            BankAccountEvent::CheckVoided {
+                check_number,
+                amount,
+                balance,
+            } => {
+                self.credit(check_number.as_str(), *amount);
+                self.balance = *balance;
+            }
+            BankAccountEvent::OverdraftFeeCharged { fee, balance } => {
+                self.debit("Overdraft_fee", *fee);
+                self.balance = *balance;
+            }
+            BankAccountEvent::AdjustmentProposed { .. } => {}
+            BankAccountEvent::AdjustmentApproved { delta, balance, .. } => {
+                if delta.is_negative() {
+                    self.debit("Reconciliation", -*delta);
+                } else {
+                    self.credit("Reconciliation", *delta);
+                }
+                self.balance = *balance;
+            }
+            BankAccountEvent::LoanDisbursed {
+                amount, balance, ..
+            } => {
+                self.credit("Loan", *amount);
+                self.balance = *balance;
+            }
+            BankAccountEvent::WireSent {
+                amount,
+                fee,
+                balance,
+                ..
+            } => {
+                self.debit("Wire", *amount);
+                self.debit("Wire_fee", *fee);
+                self.balance = *balance;
+            }
+            BankAccountEvent::BookTransferSent {
+                amount, balance, ..
+            } => {
+                self.debit("Book_transfer", *amount);
+                self.balance = *balance;
+            }
+            BankAccountEvent::BookTransferReceived {
+                amount, balance, ..
+            } => {
+                self.credit("Book_transfer", *amount);
+                self.balance = *balance;
+            }
+            BankAccountEvent::BookTransferReversed {
+                amount, balance, ..
+            } => {
+                self.credit("Book_transfer_reversal", *amount);
+                self.balance = *balance;
+            }
+            BankAccountEvent::DirectDebitReturned {
+                amount, balance, ..
+            } => {
+                self.credit("Direct_debit_return", *amount);
+                self.balance = *balance;
+            }
+            BankAccountEvent::DirectDebitRetried {
+                amount, balance, ..
+            } => {
+                self.debit("Direct_debit", *amount);
+                self.balance = *balance;
+            }
+            BankAccountEvent::ScheduledPaymentMade {
+                amount, balance, ..
+            } => {
+                self.debit("Bill_payment", *amount);
+                self.balance = *balance;
+            }
+            BankAccountEvent::AccountNoteAdded { .. }
+            | BankAccountEvent::JointHolderAdded { .. }
+            | BankAccountEvent::PaperlessEnrolled
+            | BankAccountEvent::PaperlessDisenrolled
+            | BankAccountEvent::StatementDelivered { .. }
+            | BankAccountEvent::KycStatusRecorded { .. }
+            | BankAccountEvent::SpendAlertSet { .. }
+            | BankAccountEvent::SpendAlertTriggered { .. }
+            | BankAccountEvent::LowBalanceThresholdSet { .. }
+            | BankAccountEvent::LowBalanceWarningTriggered { .. }
+            | BankAccountEvent::PaymentScheduled { .. }
+            | BankAccountEvent::PaymentFailed { .. }
+            | BankAccountEvent::TransactionFlagged { .. }
+            | BankAccountEvent::FlagCleared { .. }
+            | BankAccountEvent::AccountClosed { .. }
+            | BankAccountEvent::CurrencyBackfilled { .. }
+            | BankAccountEvent::CheckDepositCleared { .. }
+            | BankAccountEvent::OverdraftPolicySet { .. }
+            | BankAccountEvent::CommandProcessed { .. } => {}
+        }
+    }
+}
+
+// The notes that support agents have placed on an account. These are kept out of
+// the `BankAccountView` since that is returned to the account holder.
+pub type AccountNotesQuery = GenericQuery<
+    PostgresViewRepository<AccountNotesView, BankAccount>,
+    AccountNotesView,
+    BankAccount,
+>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AccountNotesView {
+    #[serde(default)]
+    applied: AppliedSequence,
+    notes: Vec<AccountNote>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountNote {
+    note: String,
+    timestamp: DateTime<Utc>,
+}
+
+impl AccountNotesView {
+    pub fn notes(&self) -> &[AccountNote] {
+        &self.notes
+    }
+}
+
+impl AccountNote {
+    pub fn note(&self) -> &str {
+        &self.note
+    }
+
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+}
+
+impl View<BankAccount> for AccountNotesView {
+    fn update(&mut self, event: &EventEnvelope<BankAccount>) {
+        if !self.applied.advance(event.sequence) {
+            return;
+        }
+        if let BankAccountEvent::AccountNoteAdded { note, timestamp } = &event.payload {
+            self.notes.push(AccountNote {
+                note: note.clone(),
+                timestamp: *timestamp,
+            });
+        }
+    }
+}
+
+impl Display for TAccountView {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:<36}| {:<36}", "Debits", "Credits")?;
+        writeln!(f, "{:-<36}+{:-<37}", "", "")?;
+        let rows = self.debits.len().max(self.credits.len());
+        for row in 0..rows {
+            let debit = self.debits.get(row).map(format_t_account_entry);
+            let credit = self.credits.get(row).map(format_t_account_entry);
+            writeln!(
+                f,
+                "{:<36}| {:<36}",
+                debit.unwrap_or_default(),
+                credit.unwrap_or_default()
+            )?;
+        }
+        writeln!(f, "{:-<36}+{:-<37}", "", "")?;
+        writeln!(
+            f,
+            "{:<36}| {:<36}",
+            format!("Total {}", self.total_debits),
+            format!("Total {}", self.total_credits)
+        )?;
+        write!(f, "Balance {}", self.balance)
+    }
+}
+
+fn format_t_account_entry(entry: &TAccountEntry) -> String {
+    format!(
+        "{:<14} {:>10} {:>10}",
+        entry.description, entry.amount, entry.running_total
+    )
+}
+
+// Records the balance at the end of each reporting period for regulators. A period
+// without any activity carries forward the balance from the period before it.
+// Works like a `GenericQuery` except that a new view is created for the
+// configured period rather than by `Default`, which would always be quarterly.
+pub struct RegulatorySnapshotQuery<
+    R: ViewRepository<RegulatorySnapshotView, BankAccount> = PostgresViewRepository<
+        RegulatorySnapshotView,
+        BankAccount,
+    >,
+> {
+    view_repository: Arc<R>,
+    period: SnapshotPeriod,
+    error_handler: Option<Box<QueryErrorHandler>>,
+}
+
+impl<R: ViewRepository<RegulatorySnapshotView, BankAccount>> RegulatorySnapshotQuery<R> {
+    pub fn new(view_repository: Arc<R>, period: SnapshotPeriod) -> Self {
+        Self {
+            view_repository,
+            period,
+            error_handler: None,
+        }
+    }
+
+    pub fn use_error_handler(&mut self, error_handler: Box<QueryErrorHandler>) {
+        self.error_handler = Some(error_handler);
+    }
+
+    pub async fn load(&self, view_id: &str) -> Option<RegulatorySnapshotView> {
+        match self.view_repository.load(view_id).await {
+            Ok(view) => view,
+            Err(err) => {
+                self.handle_error(err);
+                None
+            }
+        }
+    }
+
+    async fn apply_events(
+        &self,
+        view_id: &str,
+        events: &[EventEnvelope<BankAccount>],
+    ) -> Result<(), PersistenceError> {
+        let (mut view, context) = match self.view_repository.load_with_context(view_id).await? {
+            Some(stored) => stored,
+            None => (
+                RegulatorySnapshotView::new(self.period),
+                ViewContext::new(view_id.to_string(), 0),
+            ),
+        };
+        for event in events {
+            view.update(event);
+        }
+        self.view_repository.update_view(view, context).await
+    }
+
+    fn handle_error(&self, err: PersistenceError) {
+        if let Some(handler) = &self.error_handler {
+            (handler)(err);
+        }
+    }
+}
+
+#[async_trait]
+impl<R: ViewRepository<RegulatorySnapshotView, BankAccount>> Query<BankAccount>
+    for RegulatorySnapshotQuery<R>
+{
+    async fn dispatch(&self, aggregate_id: &str, events: &[EventEnvelope<BankAccount>]) {
+        if let Err(err) = self.apply_events(aggregate_id, events).await {
+            self.handle_error(err);
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SnapshotPeriod {
+    Monthly,
+    #[default]
+    Quarterly,
+}
+
+impl SnapshotPeriod {
+    // A sequential number for the period containing `time`, consecutive periods
+    // have consecutive numbers.
+    fn index(&self, time: DateTime<Utc>) -> i32 {
+        match self {
+            SnapshotPeriod::Monthly => time.year() * 12 + time.month0() as i32,
+            SnapshotPeriod::Quarterly => time.year() * 4 + (time.month0() / 3) as i32,
+        }
+    }
+
+    // Formats a period as `2023-03` (monthly) or `2023-Q1` (quarterly).
+    fn label(&self, index: i32) -> String {
+        match self {
+            SnapshotPeriod::Monthly => format!("{}-{:02}", index / 12, index % 12 + 1),
+            SnapshotPeriod::Quarterly => format!("{}-Q{}", index / 4, index % 4 + 1),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct RegulatorySnapshotView {
+    #[serde(default)]
+    applied: AppliedSequence,
+    period: SnapshotPeriod,
+    snapshots: BTreeMap<String, Money>,
+    last_period: Option<i32>,
+}
+
+impl RegulatorySnapshotView {
+    pub fn new(period: SnapshotPeriod) -> Self {
+        Self {
+            period,
+            ..Default::default()
+        }
+    }
+
+    // Period-end balances keyed by period label.
+    pub fn snapshots(&self) -> &BTreeMap<String, Money> {
+        &self.snapshots
+    }
+}
+
+impl View<BankAccount> for RegulatorySnapshotView {
+    fn update(&mut self, event: &EventEnvelope<BankAccount>) {
+        if !self.applied.advance(event.sequence) {
+            return;
+        }
+        let (balance, time) = match (event.payload.balance(), event_time(event)) {
+            (Some(balance), Some(time)) => (balance, time),
+            _ => return,
+        };
+        let current = self.period.index(time);
+        if let Some(last) = self.last_period {
+            let carried = self.snapshots[&self.period.label(last)];
+            for inactive in last + 1..current {
+                self.snapshots.insert(self.period.label(inactive), carried);
+            }
+        }
+        self.snapshots.insert(self.period.label(current), balance);
+        self.last_period = Some(current);
+    }
+}
+
+// Counts the account's transactions by the day of the week they occurred on, for
+// behavioral analytics.
+pub type WeekdayActivityQuery = GenericQuery<
+    PostgresViewRepository<WeekdayActivityView, BankAccount>,
+    WeekdayActivityView,
+    BankAccount,
+>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WeekdayActivityView {
+    #[serde(default)]
+    applied: AppliedSequence,
+    counts: [u32; 7],
+}
+
+impl WeekdayActivityView {
+    // Transaction counts indexed Monday (0) through Sunday (6).
+    pub fn counts(&self) -> [u32; 7] {
+        self.counts
+    }
+}
+
+impl View<BankAccount> for WeekdayActivityView {
+    fn update(&mut self, event: &EventEnvelope<BankAccount>) {
+        if !self.applied.advance(event.sequence) {
+            return;
+        }
+        // Any event that changes the balance is a transaction, each is counted
+        // even when several share the same timestamp.
+        if event.payload.balance().is_none() {
+            return;
+        }
+        if let Some(time) = event_time(event) {
+            self.counts[time.weekday().num_days_from_monday() as usize] += 1;
+        }
+    }
+}
+
+// Finds the longest period without any transactions, for dormancy analysis.
+pub type GapQuery =
+    GenericQuery<PostgresViewRepository<GapView, BankAccount>, GapView, BankAccount>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GapView {
+    #[serde(default)]
+    applied: AppliedSequence,
+    last_transaction: Option<(usize, DateTime<Utc>)>,
+    longest_gap: Option<ActivityGap>,
+}
+
+// The period between two consecutive transactions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActivityGap {
+    pub from_sequence: usize,
+    pub to_sequence: usize,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+impl ActivityGap {
+    pub fn duration(&self) -> Duration {
+        self.to - self.from
+    }
+}
+
+impl GapView {
+    // Returns `None` until at least two transactions have occurred.
+    pub fn longest_gap(&self) -> Option<&ActivityGap> {
+        self.longest_gap.as_ref()
+    }
+}
+
+impl View<BankAccount> for GapView {
+    fn update(&mut self, event: &EventEnvelope<BankAccount>) {
+        if !self.applied.advance(event.sequence) {
+            return;
+        }
+        if event.payload.balance().is_none() {
+            return;
+        }
+        let time = match event_time(event) {
+            Some(time) => time,
+            None => return,
+        };
+        if let Some((from_sequence, from)) = self.last_transaction {
+            let gap = ActivityGap {
+                from_sequence,
+                to_sequence: event.sequence,
+                from,
+                to: time,
+            };
+            let longer = match &self.longest_gap {
+                Some(longest) => gap.duration() > longest.duration(),
+                None => true,
+            };
+            if longer {
+                self.longest_gap = Some(gap);
+            }
+        }
+        self.last_transaction = Some((event.sequence, time));
+    }
+}
+
+// Pairs each withdrawal with the most recent deposit before it to estimate how
+// long deposited funds are held, for liquidity modeling. Withdrawals made before
+// any deposit cannot be paired and are only counted.
+pub type FlowTimingQuery =
+    GenericQuery<PostgresViewRepository<FlowTimingView, BankAccount>, FlowTimingView, BankAccount>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FlowTimingView {
+    #[serde(default)]
+    applied: AppliedSequence,
+    last_deposit: Option<DateTime<Utc>>,
+    holding_periods: Vec<HoldingPeriod>,
+    unpaired_withdrawals: usize,
+}
+
+// A withdrawal and the most recent deposit preceding it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HoldingPeriod {
+    pub deposited: DateTime<Utc>,
+    pub withdrawn: DateTime<Utc>,
+}
+
+impl HoldingPeriod {
+    pub fn duration(&self) -> Duration {
+        self.withdrawn - self.deposited
+    }
+}
+
+impl FlowTimingView {
+    pub fn holding_periods(&self) -> &[HoldingPeriod] {
+        &self.holding_periods
+    }
+
+    pub fn unpaired_withdrawals(&self) -> usize {
+        self.unpaired_withdrawals
+    }
+
+    // Returns `None` until a withdrawal has been paired with a deposit.
+    pub fn average_holding_time(&self) -> Option<Duration> {
+        if self.holding_periods.is_empty() {
+            return None;
+        }
+        let total = self
+            .holding_periods
+            .iter()
+            .fold(Duration::zero(), |total, period| total + period.duration());
+        Some(total / self.holding_periods.len() as i32)
+    }
+}
+
+impl View<BankAccount> for FlowTimingView {
+    fn update(&mut self, event: &EventEnvelope<BankAccount>) {
+        if !self.applied.advance(event.sequence) {
+            return;
+        }
+        let time = match event_time(event) {
+            Some(time) => time,
+            None => return,
+        };
+        match &event.payload {
+            BankAccountEvent::CustomerDepositedMoney { .. }
+            | BankAccountEvent::CheckImageDeposited { .. } => {
+                self.last_deposit = Some(time);
+            }
+            BankAccountEvent::CustomerWithdrewCash { .. }
+            | BankAccountEvent::AtmWithdrawal { .. } => match self.last_deposit {
+                Some(deposited) => self.holding_periods.push(HoldingPeriod {
+                    deposited,
+                    withdrawn: time,
+                }),
+                None => self.unpaired_withdrawals += 1,
+            },
+            _ => {}
+        }
+    }
+}
+
+// Records the closing balance of each day with activity, for risk modeling of
+// how much the balance fluctuates.
+pub type VolatilityQuery =
+    GenericQuery<PostgresViewRepository<VolatilityView, BankAccount>, VolatilityView, BankAccount>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct VolatilityView {
+    #[serde(default)]
+    applied: AppliedSequence,
+    closing_balances: BTreeMap<NaiveDate, Money>,
+}
+
+impl VolatilityView {
+    // The population standard deviation of the daily closing balances from `from`
+    // to `to` inclusive. A day without activity closes at the previous day's
+    // balance, days before the account's first transaction are not counted.
+    // Returns `None` if there is no balance in the period.
+    pub fn volatility(&self, from: NaiveDate, to: NaiveDate) -> Option<f64> {
+        let mut close = self
+            .closing_balances
+            .range(..from)
+            .next_back()
+            .map(|(_, balance)| f64::from(*balance));
+        let mut closes = Vec::new();
+        for day in from.iter_days().take_while(|day| *day <= to) {
+            if let Some(balance) = self.closing_balances.get(&day) {
+                close = Some(f64::from(*balance));
+            }
+            closes.extend(close);
+        }
+        if closes.is_empty() {
+            return None;
+        }
+        let count = closes.len() as f64;
+        let mean = closes.iter().sum::<f64>() / count;
+        let variance = closes
+            .iter()
+            .map(|balance| (balance - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        Some(variance.sqrt())
+    }
+}
+
+impl View<BankAccount> for VolatilityView {
+    fn update(&mut self, event: &EventEnvelope<BankAccount>) {
+        if !self.applied.advance(event.sequence) {
+            return;
+        }
+        if let (Some(balance), Some(time)) = (event.payload.balance(), event_time(event)) {
+            self.closing_balances.insert(time.date_naive(), balance);
+        }
+    }
+}
+
+// The time-weighted return of an account treated as an investment. External
+// flows (deposits, withdrawals, checks, etc.) divide the history into
+// sub-periods, the return of each sub-period comes only from growth (bonuses
+// and interest) and the sub-period returns are chained so that the size and timing of the
+// external flows do not affect the result.
+pub type TimeWeightedReturnQuery = GenericQuery<
+    PostgresViewRepository<TimeWeightedReturnView, BankAccount>,
+    TimeWeightedReturnView,
+    BankAccount,
+>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TimeWeightedReturnView {
+    #[serde(default)]
+    applied: AppliedSequence,
+    growth_returns: Vec<f64>,
+}
+
+impl TimeWeightedReturnView {
+    // The return as a fraction, e.g., 0.05 for 5%. An account with no growth
+    // (including one with only a single period) has a return of zero.
+    pub fn time_weighted_return(&self) -> f64 {
+        self.growth_returns
+            .iter()
+            .fold(1.0, |growth, sub_period| growth * (1.0 + sub_period))
+            - 1.0
+    }
+}
+
+impl View<BankAccount> for TimeWeightedReturnView {
+    fn update(&mut self, event: &EventEnvelope<BankAccount>) {
+        if !self.applied.advance(event.sequence) {
+            return;
+        }
+        let (amount, balance) = match &event.payload {
+            BankAccountEvent::IssuedBonus {
+                amount, balance, ..
+            }
+            | BankAccountEvent::InterestAccrued { amount, balance } => (amount, balance),
+            _ => return,
+        };
+        // Growth on an empty or overdrawn balance has no meaningful return.
+        let starting = *balance - *amount;
+        if starting.is_positive() {
+            self.growth_returns
+                .push(f64::from(*amount) / f64::from(starting));
+        }
+    }
+}
+
+// The transactions currently flagged as suspicious and awaiting AML review.
+pub type FlaggedTransactionsQuery = GenericQuery<
+    PostgresViewRepository<FlaggedTransactionsView, BankAccount>,
+    FlaggedTransactionsView,
+    BankAccount,
+>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FlaggedTransactionsView {
+    #[serde(default)]
+    applied: AppliedSequence,
+    flagged: BTreeMap<usize, String>,
+}
+
+impl FlaggedTransactionsView {
+    // The reason each flagged transaction was flagged, by sequence.
+    pub fn flagged(&self) -> &BTreeMap<usize, String> {
+        &self.flagged
+    }
+}
+
+impl View<BankAccount> for FlaggedTransactionsView {
+    fn update(&mut self, event: &EventEnvelope<BankAccount>) {
+        if !self.applied.advance(event.sequence) {
+            return;
+        }
+        match &event.payload {
+            BankAccountEvent::TransactionFlagged {
+                target_sequence,
+                reason,
+            } => {
+                self.flagged.insert(*target_sequence, reason.clone());
+            }
+            BankAccountEvent::FlagCleared { target_sequence } => {
+                self.flagged.remove(target_sequence);
+            }
+            _ => {}
+        }
+    }
+}
+
+// The balance after every balance-changing event, by sequence, for debugging
+// and point-in-time queries.
+pub type SequenceBalanceQuery = GenericQuery<
+    PostgresViewRepository<SequenceBalanceView, BankAccount>,
+    SequenceBalanceView,
+    BankAccount,
+>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SequenceBalanceView {
+    #[serde(default)]
+    applied: AppliedSequence,
+    balances: BTreeMap<usize, Money>,
+}
+
+impl SequenceBalanceView {
+    // The balance as of `sequence`, i.e., after the latest balance-changing
+    // event at or before it. Returns `None` before the first such event.
+    pub fn balance_at(&self, sequence: usize) -> Option<Money> {
+        self.balances
+            .range(..=sequence)
+            .next_back()
+            .map(|(_, balance)| *balance)
+    }
+}
+
+impl View<BankAccount> for SequenceBalanceView {
+    fn update(&mut self, event: &EventEnvelope<BankAccount>) {
+        if !self.applied.advance(event.sequence) {
+            return;
+        }
+        if let Some(balance) = event.payload.balance() {
+            self.balances.insert(event.sequence, balance);
+        }
+    }
+}
+
+// The fees charged each month relative to the account's average balance that
+// month, for fairness monitoring. Wire and overdraft fees are the fees charged.
+// The average balance is the mean of the balances following each transaction
+// in the month.
+pub type FeeBurdenQuery =
+    GenericQuery<PostgresViewRepository<FeeBurdenView, BankAccount>, FeeBurdenView, BankAccount>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FeeBurdenView {
+    #[serde(default)]
+    applied: AppliedSequence,
+    // Keyed by month, e.g., "2023-03".
+    months: BTreeMap<String, MonthlyFees>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct MonthlyFees {
+    fees: Money,
+    balance_total: Money,
+    transactions: u32,
+}
+
+impl MonthlyFees {
+    fn ratio(&self) -> Option<f64> {
+        if self.transactions == 0 {
+            return None;
+        }
+        let average_balance = f64::from(self.balance_total) / self.transactions as f64;
+        if average_balance <= 0.0 {
+            return None;
+        }
+        Some(f64::from(self.fees) / average_balance)
+    }
+}
+
+impl FeeBurdenView {
+    // The ratio of fees to average balance for a month, `None` if there was no
+    // positive balance that month.
+    pub fn ratio(&self, month: &str) -> Option<f64> {
+        self.months.get(month).and_then(MonthlyFees::ratio)
+    }
+
+    // The months whose fees exceed `threshold` of the average balance, in order.
+    pub fn months_over(&self, threshold: f64) -> Vec<&str> {
+        self.months
+            .iter()
+            .filter(|(_, month)| month.ratio().is_some_and(|ratio| ratio > threshold))
+            .map(|(label, _)| label.as_str())
+            .collect()
+    }
+}
+
+impl View<BankAccount> for FeeBurdenView {
+    fn update(&mut self, event: &EventEnvelope<BankAccount>) {
+        if !self.applied.advance(event.sequence) {
+            return;
+        }
+        let (balance, time) = match (event.payload.balance(), event_time(event)) {
+            (Some(balance), Some(time)) => (balance, time),
+            _ => return,
+        };
+        let month = self
+            .months
+            .entry(format!("{}-{:02}", time.year(), time.month()))
+            .or_default();
+        if let BankAccountEvent::WireSent { fee, .. }
+        | BankAccountEvent::OverdraftFeeCharged { fee, .. } = &event.payload
+        {
+            month.fees += *fee;
+        }
+        month.balance_total += balance;
+        month.transactions += 1;
+    }
+}
+
+// Projects when the balance will reach a savings goal from the account's recent
+// net inflow, i.e., the monthly change in balance, so withdrawals and fees count
+// against progress towards the goal.
+pub type GoalProjectionQuery = GenericQuery<
+    PostgresViewRepository<GoalProjectionView, BankAccount>,
+    GoalProjectionView,
+    BankAccount,
+>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GoalProjectionView {
+    #[serde(default)]
+    applied: AppliedSequence,
+    balance: Money,
+    last_updated: Option<NaiveDate>,
+    // Keyed by the first day of the month.
+    monthly_inflows: BTreeMap<NaiveDate, Money>,
+}
+
+impl GoalProjectionView {
+    // The average net inflow per month over the `window` months ending with the
+    // month of the latest transaction, months without transactions count as zero.
+    pub fn average_monthly_inflow(&self, window: u32) -> Option<f64> {
+        let last_month = self.last_updated?.with_day(1)?;
+        let first_month = last_month.checked_sub_months(Months::new(window.checked_sub(1)?))?;
+        let total: Money = self
+            .monthly_inflows
+            .range(first_month..=last_month)
+            .map(|(_, inflow)| *inflow)
+            .sum();
+        Some(f64::from(total) / window as f64)
+    }
+
+    // The number of months until the balance reaches `target` at the average
+    // inflow over `window` months, rounded up. Returns `None` if the goal has not
+    // been reached and the average inflow is not positive.
+    pub fn months_to_goal(&self, target: Money, window: u32) -> Option<u32> {
+        if self.balance >= target {
+            return Some(0);
+        }
+        let inflow = self.average_monthly_inflow(window)?;
+        if inflow <= 0.0 {
+            return None;
+        }
+        Some((f64::from(target - self.balance) / inflow).ceil() as u32)
+    }
+
+    // The date the balance is projected to reach `target`, counted in months
+    // from the latest transaction.
+    pub fn projected_date(&self, target: Money, window: u32) -> Option<NaiveDate> {
+        let months = self.months_to_goal(target, window)?;
+        self.last_updated?.checked_add_months(Months::new(months))
+    }
+}
+
+impl View<BankAccount> for GoalProjectionView {
+    fn update(&mut self, event: &EventEnvelope<BankAccount>) {
+        if !self.applied.advance(event.sequence) {
+            return;
+        }
+        let (balance, time) = match (event.payload.balance(), event_time(event)) {
+            (Some(balance), Some(time)) => (balance, time),
+            _ => return,
+        };
+        let date = time.date_naive();
+        if let Some(month) = date.with_day(1) {
+            *self.monthly_inflows.entry(month).or_default() += balance - self.balance;
+        }
+        self.balance = balance;
+        self.last_updated = Some(date);
+    }
+}
+
+// Tracks who the account holder has paid by check, for spending insights.
+// Checks written without a payee are not included.
+pub type PayeeQuery =
+    GenericQuery<PostgresViewRepository<PayeeView, BankAccount>, PayeeView, BankAccount>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PayeeView {
+    #[serde(default)]
+    applied: AppliedSequence,
+    totals: BTreeMap<String, Money>,
+}
+
+impl PayeeView {
+    pub fn distinct_payee_count(&self) -> usize {
+        self.totals.len()
+    }
+
+    // The total paid to a payee, zero if they have never been paid.
+    pub fn paid_to(&self, payee: &str) -> Money {
+        self.totals.get(payee).copied().unwrap_or_default()
+    }
+}
+
+impl View<BankAccount> for PayeeView {
+    fn update(&mut self, event: &EventEnvelope<BankAccount>) {
+        if !self.applied.advance(event.sequence) {
+            return;
+        }
+        if let BankAccountEvent::CustomerWroteCheck {
+            amount,
+            payee: Some(payee),
+            ..
+        } = &event.payload
+        {
+            *self.totals.entry(payee.clone()).or_default() += *amount;
+        }
+    }
+}
+
+// The most recent ATM withdrawal locations, used for velocity and geographic
+// fraud checks.
+pub type AtmLocationQuery = GenericQuery<
+    PostgresViewRepository<AtmLocationView, BankAccount>,
+    AtmLocationView,
+    BankAccount,
+>;
+
+// The number of ATM withdrawals retained by the `AtmLocationView`.
+const RECENT_ATM_LOCATIONS: usize = 10;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AtmLocationView {
+    #[serde(default)]
+    applied: AppliedSequence,
+    recent: Vec<AtmLocation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AtmLocation {
+    pub atm_id: String,
+    pub location: String,
+    pub amount: Money,
+    pub time: Option<DateTime<Utc>>,
+}
+
+impl AtmLocationView {
+    // Recent ATM withdrawals, oldest first.
+    pub fn recent(&self) -> &[AtmLocation] {
+        &self.recent
+    }
+}
+
+impl View<BankAccount> for AtmLocationView {
+    fn update(&mut self, event: &EventEnvelope<BankAccount>) {
+        if !self.applied.advance(event.sequence) {
+            return;
+        }
+        if let BankAccountEvent::AtmWithdrawal {
+            amount,
+            atm_id,
+            location,
+            ..
+        } = &event.payload
+        {
+            if self.recent.len() == RECENT_ATM_LOCATIONS {
+                self.recent.remove(0);
+            }
+            self.recent.push(AtmLocation {
+                atm_id: atm_id.clone(),
+                location: location.clone(),
+                amount: *amount,
+                time: event_time(event),
+            });
+        }
+    }
+}
+
+// A statement of the account: every transaction that changed the balance, in
+// sequence order, with the balance it left behind.
+pub type TransactionHistoryQuery = GenericQuery<
+    PostgresViewRepository<TransactionHistoryView, BankAccount>,
+    TransactionHistoryView,
+    BankAccount,
+>;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TransactionHistoryView {
+    history: Vec<TransactionRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TransactionRecord {
+    pub kind: String,
+    pub amount: Money,
+    pub balance_after: Money,
+    pub sequence: usize,
+}
+
+impl TransactionHistoryView {
+    pub fn history(&self) -> &[TransactionRecord] {
+        &self.history
+    }
+}
+
+impl View<BankAccount> for TransactionHistoryView {
+    fn update(&mut self, event: &EventEnvelope<BankAccount>) {
+        let (Some((kind, amount)), Some(balance_after)) =
+            (transaction(&event.payload), event.payload.balance())
+        else {
+            return;
+        };
+        // Events normally arrive in order, one that does not is put in its place
+        // rather than appended. Unlike the other views this one keeps the
+        // sequence of every record, so it needs no `AppliedSequence` to skip an
+        // event it has already applied.
+        let index = self
+            .history
+            .partition_point(|record| record.sequence < event.sequence);
+        if self.history.get(index).map(|record| record.sequence) == Some(event.sequence) {
+            return;
+        }
+        self.history.insert(
+            index,
+            TransactionRecord {
+                kind: kind.to_string(),
+                amount,
+                balance_after,
+                sequence: event.sequence,
+            },
+        );
+    }
+}
+
+// The kind and amount of the transaction an event records, if any. The amount is
+// the total moved, e.g., a wire includes its fee.
+fn transaction(event: &BankAccountEvent) -> Option<(&'static str, Money)> {
+    match event {
+        BankAccountEvent::CustomerDepositedMoney { amount, .. } => Some(("deposit", *amount)),
+        BankAccountEvent::CustomerWithdrewCash { amount, .. }
+        | BankAccountEvent::AtmWithdrawal { amount, .. } => Some(("withdrawal", *amount)),
+        BankAccountEvent::CheckImageDeposited { amount, .. } => Some(("check deposit", *amount)),
+        BankAccountEvent::CheckDepositRejected { amount, .. } => Some(("check return", *amount)),
+        BankAccountEvent::PurchaseWithCashback {
+            purchase_amount,
+            cashback_amount,
+            ..
+        } => Some(("purchase", *purchase_amount + *cashback_amount)),
+        BankAccountEvent::CustomerWroteCheck { amount, .. } => Some(("check", *amount)),
+        BankAccountEvent::CheckVoided { amount, .. } => Some(("check void", *amount)),
+        BankAccountEvent::OverdraftFeeCharged { fee, .. } => Some(("overdraft fee", *fee)),
+        BankAccountEvent::IssuedBonus { amount, .. } => Some(("bonus", *amount)),
+        BankAccountEvent::InterestAccrued { amount, .. } => Some(("interest", *amount)),
+        BankAccountEvent::AdjustmentApproved { delta, .. } => Some(("adjustment", *delta)),
+        BankAccountEvent::LoanDisbursed { amount, .. } => Some(("loan", *amount)),
+        BankAccountEvent::WireSent { amount, fee, .. } => Some(("wire", *amount + *fee)),
+        BankAccountEvent::BookTransferSent { amount, .. } => Some(("transfer out", *amount)),
+        BankAccountEvent::BookTransferReceived { amount, .. } => Some(("transfer in", *amount)),
+        BankAccountEvent::BookTransferReversed { amount, .. } => {
+            Some(("transfer reversal", *amount))
+        }
+        BankAccountEvent::DirectDebitReturned { amount, .. } => {
+            Some(("direct debit return", *amount))
+        }
+        BankAccountEvent::DirectDebitRetried { amount, .. } => Some(("direct debit", *amount)),
+        BankAccountEvent::ScheduledPaymentMade { amount, .. } => Some(("bill payment", *amount)),
+        BankAccountEvent::AccountOpened { .. }
+        | BankAccountEvent::AdjustmentProposed { .. }
+        | BankAccountEvent::AccountNoteAdded { .. }
+        | BankAccountEvent::JointHolderAdded { .. }
+        | BankAccountEvent::PaperlessEnrolled
+        | BankAccountEvent::PaperlessDisenrolled
+        | BankAccountEvent::StatementDelivered { .. }
+        | BankAccountEvent::KycStatusRecorded { .. }
+        | BankAccountEvent::SpendAlertSet { .. }
+        | BankAccountEvent::SpendAlertTriggered { .. }
+        | BankAccountEvent::LowBalanceThresholdSet { .. }
+        | BankAccountEvent::LowBalanceWarningTriggered { .. }
+        | BankAccountEvent::PaymentScheduled { .. }
+        | BankAccountEvent::PaymentFailed { .. }
+        | BankAccountEvent::TransactionFlagged { .. }
+        | BankAccountEvent::FlagCleared { .. }
+        | BankAccountEvent::AccountClosed { .. }
+        | BankAccountEvent::CurrencyBackfilled { .. }
+        | BankAccountEvent::CheckDepositCleared { .. }
+        | BankAccountEvent::OverdraftPolicySet { .. }
+        | BankAccountEvent::CommandProcessed { .. } => None,
+    }
+}
+
+#[cfg(test)]
+mod query_tests {
+    use async_trait::async_trait;
+    use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+    use cqrs_es::persist::{PersistenceError, ViewContext, ViewRepository};
+    use cqrs_es::{EventEnvelope, Query, View};
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use crate::domain::aggregate::BankAccount;
+    use crate::domain::check_number::CheckNumber;
+    use crate::domain::events::BankAccountEvent;
+    use crate::domain::money::Money;
+    use crate::queries::{
+        next_event, sequence_gaps, AccountNotesView, ActivationView, Alert, AlertingQuery,
+        AtmLocationView, BalanceResponse, BankAccountView, ComplianceAlertNotifier,
+        DeduplicatingQuery, EventBroadcaster, FeeBurdenView, FlaggedTransactionsView,
+        FlowTimingView, GapView, GoalProjectionView, InMemoryViewRepository, Metrics,
+        MetricsQueryProcessor, PayeeView, RegulatorySnapshotQuery,
+        RegulatorySnapshotView, SequenceBalanceView, SnapshotPeriod, SpendAlertNotifier,
+        SpendAlertQuery, TAccountView, TimeWeightedReturnView, TransactionHistoryView,
+        TransactionRecord, VolatilityView, WeekdayActivityView,
+    };
+
+    // When a cash withdrawal was made, the views use the time in the metadata.
+    fn withdrawal_time() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2023, 3, 1, 9, 0, 0).unwrap()
+    }
+
+    // Wraps an event as it would be delivered to a query, stamped with the
+    // provided time in the same way the `CommandExtractor` does.
+    fn envelope(
+        sequence: usize,
+        time: &str,
+        payload: BankAccountEvent,
+    ) -> EventEnvelope<BankAccount> {
+        let mut metadata = HashMap::new();
+        metadata.insert("time".to_string(), time.to_string());
+        EventEnvelope {
+            aggregate_id: "ACCT-1".to_string(),
+            sequence,
+            payload,
+            metadata,
+        }
+    }
+
+    // Records every event it is dispatched.
+    #[derive(Clone, Default)]
+    struct RecordingQuery {
+        events: Arc<Mutex<Vec<EventEnvelope<BankAccount>>>>,
+    }
+
+    #[async_trait]
+    impl Query<BankAccount> for RecordingQuery {
+        async fn dispatch(&self, _aggregate_id: &str, events: &[EventEnvelope<BankAccount>]) {
+            self.events.lock().unwrap().extend_from_slice(events);
+        }
+    }
+
+    #[test]
+    fn test_activation_time_to_first_deposit() {
+        let mut view = ActivationView::default();
+        view.update(&envelope(
+            1,
+            "2023-03-01T09:00:00+00:00",
+            BankAccountEvent::AccountOpened {
+                account_id: "ACCT-1".to_string(),
+                currency: Some("USD".to_string()),
+                holder_name: "Jane Doe".to_string(),
+                email: None,
+            },
+        ));
+        assert_eq!(view.time_to_first_deposit(), None);
+
+        view.update(&envelope(
+            2,
+            "2023-03-02T12:30:00+00:00",
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(10_000),
+                balance: Money::from_cents(10_000),
+            },
+        ));
+        view.update(&envelope(
+            3,
+            "2023-03-05T12:30:00+00:00",
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(10_000),
+                balance: Money::from_cents(20_000),
+            },
+        ));
+        assert_eq!(
+            view.time_to_first_deposit(),
+            Some(Duration::hours(27) + Duration::minutes(30))
+        );
+    }
+
+    #[test]
+    fn test_t_account_columns_reconcile_with_balance() {
+        let events = vec![
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(50_000),
+                balance: Money::from_cents(50_000),
+            },
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(12_000),
+                balance: Money::from_cents(38_000),
+                at: withdrawal_time(),
+            },
+            BankAccountEvent::IssuedBonus {
+                bonus_id: "BON-1".to_string(),
+                amount: Money::from_cents(2_500),
+                balance: Money::from_cents(40_500),
+            },
+            BankAccountEvent::CustomerWroteCheck {
+                check_number: CheckNumber::new("1170").unwrap(),
+                amount: Money::from_cents(5_500),
+                balance: Money::from_cents(35_000),
+                payee: None,
+            },
+        ];
+        let mut view = TAccountView::default();
+        for (sequence, event) in events.into_iter().enumerate() {
+            view.update(&envelope(sequence + 1, "2023-03-01T09:00:00+00:00", event));
+        }
+
+        assert_eq!(view.total_debits(), Money::from_cents(17_500));
+        assert_eq!(view.total_credits(), Money::from_cents(52_500));
+        assert_eq!(view.balance(), Money::from_cents(35_000));
+        assert_eq!(view.total_credits() - view.total_debits(), view.balance());
+        assert!(view.to_string().ends_with("Balance 350.00"));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_query_processor() {
+        let query = MetricsQueryProcessor::default();
+        let events = [
+            envelope(
+                1,
+                "2023-03-01T09:00:00+00:00",
+                BankAccountEvent::CustomerDepositedMoney {
+                    amount: Money::from_cents(50_000),
+                    balance: Money::from_cents(50_000),
+                },
+            ),
+            envelope(
+                2,
+                "2023-03-01T09:05:00+00:00",
+                BankAccountEvent::CheckImageDeposited {
+                    amount: Money::from_cents(10_000),
+                    balance: Money::from_cents(60_000),
+                    front_image_ref: "img-front".to_string(),
+                    back_image_ref: "img-back".to_string(),
+                },
+            ),
+            envelope(
+                3,
+                "2023-03-01T09:10:00+00:00",
+                BankAccountEvent::CustomerWithdrewCash {
+                    amount: Money::from_cents(5_000),
+                    balance: Money::from_cents(55_000),
+                    at: withdrawal_time(),
+                },
+            ),
+            envelope(
+                4,
+                "2023-03-01T09:15:00+00:00",
+                BankAccountEvent::CustomerWroteCheck {
+                    check_number: CheckNumber::new("1170").unwrap(),
+                    amount: Money::from_cents(20_000),
+                    balance: Money::from_cents(35_000),
+                    payee: None,
+                },
+            ),
+            envelope(
+                5,
+                "2023-03-01T09:20:00+00:00",
+                BankAccountEvent::CheckVoided {
+                    check_number: CheckNumber::new("1170").unwrap(),
+                    amount: Money::from_cents(20_000),
+                    balance: Money::from_cents(55_000),
+                },
+            ),
+            envelope(
+                6,
+                "2023-03-01T09:25:00+00:00",
+                BankAccountEvent::PaperlessEnrolled,
+            ),
+        ];
+        query.dispatch("ACCT-1", &events[..3]).await;
+        query.dispatch("ACCT-1", &events[3..]).await;
+
+        assert_eq!(
+            query.snapshot(),
+            Metrics {
+                deposits: 2,
+                withdrawals: 1,
+                checks: 1,
+                total_deposited: Money::from_cents(60_000),
+                total_withdrawn: Money::from_cents(25_000),
+            }
+        );
+    }
+
+    #[test]
+    fn test_view_records_account_holder() {
+        let mut view = BankAccountView::default();
+        view.update(&envelope(
+            1,
+            "2023-03-01T09:00:00+00:00",
+            BankAccountEvent::AccountOpened {
+                account_id: "ACCT-1".to_string(),
+                currency: Some("USD".to_string()),
+                holder_name: "Jane Doe".to_string(),
+                email: Some("jane.doe@example.com".to_string()),
+            },
+        ));
+
+        assert_eq!(view.holder_name(), "Jane Doe");
+        assert_eq!(view.email(), Some("jane.doe@example.com"));
+        let json = serde_json::to_value(&view).unwrap();
+        assert_eq!(json["holder_name"], "Jane Doe");
+    }
+
+    #[test]
+    fn test_balance_response_json_shape() {
+        let mut view = BankAccountView::default();
+        view.update(&envelope(
+            1,
+            "2023-03-01T09:00:00+00:00",
+            BankAccountEvent::AccountOpened {
+                account_id: "ACCT-1".to_string(),
+                currency: Some("USD".to_string()),
+                holder_name: "Jane Doe".to_string(),
+                email: None,
+            },
+        ));
+        view.update(&envelope(
+            2,
+            "2023-03-02T10:15:00+00:00",
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(74_372),
+                balance: Money::from_cents(74_372),
+            },
+        ));
+
+        let json = serde_json::to_value(BalanceResponse::from(&view)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "account_id": "ACCT-1",
+                "balance": "743.72",
+                "available_balance": "743.72",
+                "currency": "USD",
+                "last_updated": "2023-03-02T10:15:00Z"
+            })
+        );
+    }
+
+    #[test]
+    fn test_account_notes_retained_in_order() {
+        let first = Utc.with_ymd_and_hms(2023, 3, 1, 9, 0, 0).unwrap();
+        let second = Utc.with_ymd_and_hms(2023, 3, 4, 16, 45, 0).unwrap();
+        let mut view = AccountNotesView::default();
+        view.update(&envelope(
+            1,
+            "2023-03-01T09:00:00+00:00",
+            BankAccountEvent::AccountNoteAdded {
+                note: "customer called about a lost card".to_string(),
+                timestamp: first,
+            },
+        ));
+        view.update(&envelope(
+            2,
+            "2023-03-04T16:45:00+00:00",
+            BankAccountEvent::AccountNoteAdded {
+                note: "replacement card mailed".to_string(),
+                timestamp: second,
+            },
+        ));
+
+        let notes = view.notes();
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0].note(), "customer called about a lost card");
+        assert_eq!(notes[0].timestamp(), first);
+        assert_eq!(notes[1].note(), "replacement card mailed");
+        assert_eq!(notes[1].timestamp(), second);
+    }
+
+    #[test]
+    fn test_regulatory_snapshots_carry_forward() {
+        let mut view = RegulatorySnapshotView::new(SnapshotPeriod::Quarterly);
+        view.update(&envelope(
+            1,
+            "2023-01-10T09:00:00+00:00",
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(30_000),
+                balance: Money::from_cents(30_000),
+            },
+        ));
+        view.update(&envelope(
+            2,
+            "2023-02-20T09:00:00+00:00",
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(5_000),
+                balance: Money::from_cents(25_000),
+                at: withdrawal_time(),
+            },
+        ));
+        view.update(&envelope(
+            3,
+            "2023-08-05T09:00:00+00:00",
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(10_000),
+                balance: Money::from_cents(35_000),
+            },
+        ));
+        view.update(&envelope(
+            4,
+            "2023-12-31T23:00:00+00:00",
+            BankAccountEvent::AccountNoteAdded {
+                note: "year end review".to_string(),
+                timestamp: Utc.with_ymd_and_hms(2023, 12, 31, 23, 0, 0).unwrap(),
+            },
+        ));
+
+        let snapshots: Vec<(&str, Money)> = view
+            .snapshots()
+            .iter()
+            .map(|(period, balance)| (period.as_str(), *balance))
+            .collect();
+        assert_eq!(
+            snapshots,
+            vec![
+                ("2023-Q1", Money::from_cents(25_000)),
+                ("2023-Q2", Money::from_cents(25_000)),
+                ("2023-Q3", Money::from_cents(35_000))
+            ]
+        );
+    }
+
+    // Holds regulatory snapshot views in memory in place of Postgres.
+    #[derive(Default)]
+    struct SnapshotViewRepository {
+        views: Mutex<HashMap<String, RegulatorySnapshotView>>,
+    }
+
+    #[async_trait]
+    impl ViewRepository<RegulatorySnapshotView, BankAccount> for SnapshotViewRepository {
+        async fn load(
+            &self,
+            view_id: &str,
+        ) -> Result<Option<RegulatorySnapshotView>, PersistenceError> {
+            Ok(self.views.lock().unwrap().get(view_id).cloned())
+        }
+
+        async fn load_with_context(
+            &self,
+            view_id: &str,
+        ) -> Result<Option<(RegulatorySnapshotView, ViewContext)>, PersistenceError> {
+            let view = self.load(view_id).await?;
+            Ok(view.map(|view| (view, ViewContext::new(view_id.to_string(), 0))))
+        }
+
+        async fn update_view(
+            &self,
+            view: RegulatorySnapshotView,
+            context: ViewContext,
+        ) -> Result<(), PersistenceError> {
+            self.views
+                .lock()
+                .unwrap()
+                .insert(context.view_instance_id, view);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_regulatory_snapshot_query_uses_configured_period() {
+        let query = RegulatorySnapshotQuery::new(
+            Arc::new(SnapshotViewRepository::default()),
+            SnapshotPeriod::Monthly,
+        );
+        let deposit = |amount: Money, balance: Money| BankAccountEvent::CustomerDepositedMoney {
+            amount,
+            balance,
+        };
+        query
+            .dispatch(
+                "ACCT-1",
+                &[envelope(
+                    1,
+                    "2023-01-10T09:00:00+00:00",
+                    deposit(Money::from_cents(30_000), Money::from_cents(30_000)),
+                )],
+            )
+            .await;
+        query
+            .dispatch(
+                "ACCT-1",
+                &[envelope(
+                    2,
+                    "2023-03-05T09:00:00+00:00",
+                    deposit(Money::from_cents(5_000), Money::from_cents(35_000)),
+                )],
+            )
+            .await;
+
+        let view = query.load("ACCT-1").await.unwrap();
+        let snapshots: Vec<(&str, Money)> = view
+            .snapshots()
+            .iter()
+            .map(|(period, balance)| (period.as_str(), *balance))
+            .collect();
+        assert_eq!(
+            snapshots,
+            vec![
+                ("2023-01", Money::from_cents(30_000)),
+                ("2023-02", Money::from_cents(30_000)),
+                ("2023-03", Money::from_cents(35_000))
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deduplicating_query_drops_repeated_sequence() {
+        let deposit = |amount: Money, balance: Money| BankAccountEvent::CustomerDepositedMoney {
+            amount,
+            balance,
+        };
+        let recorder = RecordingQuery::default();
+        let query = DeduplicatingQuery::new(recorder.clone(), 100);
+        query
+            .dispatch(
+                "ACCT-1",
+                &[
+                    envelope(
+                        1,
+                        "2023-03-01T09:00:00+00:00",
+                        deposit(Money::from_cents(1_000), Money::from_cents(1_000)),
+                    ),
+                    envelope(
+                        2,
+                        "2023-03-01T09:01:00+00:00",
+                        deposit(Money::from_cents(2_000), Money::from_cents(3_000)),
+                    ),
+                    envelope(
+                        2,
+                        "2023-03-01T09:01:00+00:00",
+                        deposit(Money::from_cents(9_900), Money::from_cents(12_900)),
+                    ),
+                ],
+            )
+            .await;
+        query
+            .dispatch(
+                "ACCT-1",
+                &[
+                    envelope(
+                        2,
+                        "2023-03-01T09:01:00+00:00",
+                        deposit(Money::from_cents(2_000), Money::from_cents(3_000)),
+                    ),
+                    envelope(
+                        3,
+                        "2023-03-01T09:02:00+00:00",
+                        deposit(Money::from_cents(500), Money::from_cents(3_500)),
+                    ),
+                ],
+            )
+            .await;
+
+        let dispatched = recorder.events.lock().unwrap();
+        let sequences: Vec<usize> = dispatched.iter().map(|event| event.sequence).collect();
+        assert_eq!(sequences, vec![1, 2, 3]);
+        assert_eq!(
+            dispatched[1].payload,
+            deposit(Money::from_cents(2_000), Money::from_cents(3_000))
+        );
+    }
+
+    #[test]
+    fn test_weekday_activity_counts() {
+        let deposit = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(1_000),
+            balance: Money::from_cents(1_000),
+        };
+        let mut view = WeekdayActivityView::default();
+        // 2023-03-06 is a Monday.
+        for (sequence, time) in [
+            "2023-03-06T09:00:00+00:00",
+            "2023-03-06T09:00:00+00:00",
+            "2023-03-08T17:30:00+00:00",
+            "2023-03-12T11:00:00+00:00",
+            "2023-03-13T08:00:00+00:00",
+        ]
+        .iter()
+        .enumerate()
+        {
+            view.update(&envelope(sequence + 1, time, deposit.clone()));
+        }
+        view.update(&envelope(
+            6,
+            "2023-03-10T10:00:00+00:00",
+            BankAccountEvent::PaperlessEnrolled,
+        ));
+
+        assert_eq!(view.counts(), [3, 0, 1, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_longest_gap() {
+        let deposit = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(1_000),
+            balance: Money::from_cents(1_000),
+        };
+        let mut view = GapView::default();
+        view.update(&envelope(1, "2023-03-01T09:00:00+00:00", deposit.clone()));
+        assert_eq!(view.longest_gap(), None);
+
+        view.update(&envelope(2, "2023-03-03T09:00:00+00:00", deposit.clone()));
+        view.update(&envelope(
+            3,
+            "2023-03-04T09:00:00+00:00",
+            BankAccountEvent::PaperlessEnrolled,
+        ));
+        view.update(&envelope(4, "2023-03-13T21:00:00+00:00", deposit.clone()));
+        view.update(&envelope(5, "2023-03-14T09:00:00+00:00", deposit));
+
+        let gap = view.longest_gap().unwrap();
+        assert_eq!(gap.from_sequence, 2);
+        assert_eq!(gap.to_sequence, 4);
+        assert_eq!(gap.duration(), Duration::days(10) + Duration::hours(12));
+    }
+
+    #[test]
+    fn test_payee_totals() {
+        fn check(number: &str, amount: Money, payee: Option<&str>) -> BankAccountEvent {
+            BankAccountEvent::CustomerWroteCheck {
+                check_number: CheckNumber::new(number).unwrap(),
+                amount,
+                balance: Money::from_cents(100_000),
+                payee: payee.map(str::to_string),
+            }
+        }
+        let mut view = PayeeView::default();
+        for (sequence, event) in [
+            check("1170", Money::from_cents(12_000), Some("City Water")),
+            check("1171", Money::from_cents(4_550), Some("Corner Grocer")),
+            check("1172", Money::from_cents(8_000), Some("City Water")),
+            check("1173", Money::from_cents(1_200), None),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            view.update(&envelope(sequence + 1, "2023-03-01T09:00:00+00:00", event));
+        }
+
+        assert_eq!(view.distinct_payee_count(), 2);
+        assert_eq!(view.paid_to("City Water"), Money::from_cents(20_000));
+        assert_eq!(view.paid_to("Corner Grocer"), Money::from_cents(4_550));
+        assert_eq!(view.paid_to("Electric Co"), Money::ZERO);
+    }
+
+    #[test]
+    fn test_recent_atm_locations() {
+        let mut view = AtmLocationView::default();
+        for sequence in 1..=12 {
+            view.update(&envelope(
+                sequence,
+                "2023-03-01T09:00:00+00:00",
+                BankAccountEvent::AtmWithdrawal {
+                    amount: Money::from_cents(2_000),
+                    balance: Money::from_cents(100_000),
+                    atm_id: format!("ATM-{}", sequence),
+                    location: format!("Branch {}", sequence),
+                    at: Utc.with_ymd_and_hms(2023, 3, 1, 9, 0, 0).unwrap(),
+                },
+            ));
+        }
+
+        let recent = view.recent();
+        assert_eq!(recent.len(), 10);
+        assert_eq!(recent[0].atm_id, "ATM-3");
+        assert_eq!(recent[9].location, "Branch 12");
+    }
+
+    #[derive(Clone, Default)]
+    struct RecordingNotifier {
+        alerts: Arc<Mutex<Vec<(String, Money, Money)>>>,
+    }
+
+    #[async_trait]
+    impl SpendAlertNotifier for RecordingNotifier {
+        async fn notify(&self, account_id: &str, amount: Money, threshold: Money) {
+            self.alerts
+                .lock()
+                .unwrap()
+                .push((account_id.to_string(), amount, threshold));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_spend_alert_notifies() {
+        let notifier = RecordingNotifier::default();
+        let query = SpendAlertQuery::new(Box::new(notifier.clone()));
+        query
+            .dispatch(
+                "ACCT-1",
+                &[
+                    envelope(
+                        1,
+                        "2023-03-01T09:00:00+00:00",
+                        BankAccountEvent::CustomerWithdrewCash {
+                            amount: Money::from_cents(60_000),
+                            balance: Money::from_cents(40_000),
+                            at: withdrawal_time(),
+                        },
+                    ),
+                    envelope(
+                        2,
+                        "2023-03-01T09:00:00+00:00",
+                        BankAccountEvent::SpendAlertTriggered {
+                            amount: Money::from_cents(60_000),
+                            threshold: Money::from_cents(50_000),
+                        },
+                    ),
+                ],
+            )
+            .await;
+
+        assert_eq!(
+            *notifier.alerts.lock().unwrap(),
+            vec![(
+                "ACCT-1".to_string(),
+                Money::from_cents(60_000),
+                Money::from_cents(50_000)
+            )]
+        );
+    }
+
+    #[derive(Clone, Default)]
+    struct RecordingComplianceNotifier {
+        alerts: Arc<Mutex<Vec<Alert>>>,
+    }
+
+    impl RecordingComplianceNotifier {
+        fn alerts(&self) -> Vec<Alert> {
+            self.alerts.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl ComplianceAlertNotifier for RecordingComplianceNotifier {
+        async fn notify(&self, alert: &Alert) {
+            self.alerts.lock().unwrap().push(alert.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_alerting_query_reports_large_transactions() {
+        let notifier = RecordingComplianceNotifier::default();
+        let query = AlertingQuery::new(Money::from_cents(1_000_000), Box::new(notifier.clone()));
+        query
+            .dispatch(
+                "ACCT-1",
+                &[
+                    envelope(
+                        1,
+                        "2023-03-01T09:00:00+00:00",
+                        BankAccountEvent::CustomerDepositedMoney {
+                            amount: Money::from_cents(900_000),
+                            balance: Money::from_cents(900_000),
+                        },
+                    ),
+                    envelope(
+                        2,
+                        "2023-03-01T09:05:00+00:00",
+                        BankAccountEvent::CustomerDepositedMoney {
+                            amount: Money::from_cents(1_200_000),
+                            balance: Money::from_cents(2_100_000),
+                        },
+                    ),
+                ],
+            )
+            .await;
+
+        assert_eq!(
+            notifier.alerts(),
+            vec![Alert {
+                account_id: "ACCT-1".to_string(),
+                sequence: 2,
+                amount: Money::from_cents(1_200_000),
+                kind: "deposit".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_event_broadcaster() {
+        let broadcaster = EventBroadcaster::new(16);
+        let mut receiver = broadcaster.subscribe();
+        broadcaster
+            .dispatch(
+                "ACCT-1",
+                &[
+                    envelope(
+                        2,
+                        "2023-03-01T09:00:00+00:00",
+                        BankAccountEvent::CustomerDepositedMoney {
+                            amount: Money::from_cents(50_000),
+                            balance: Money::from_cents(50_000),
+                        },
+                    ),
+                    envelope(
+                        3,
+                        "2023-03-01T09:05:00+00:00",
+                        BankAccountEvent::CustomerWithdrewCash {
+                            amount: Money::from_cents(12_000),
+                            balance: Money::from_cents(38_000),
+                            at: withdrawal_time(),
+                        },
+                    ),
+                ],
+            )
+            .await;
+
+        let deposit = next_event(&mut receiver).await.unwrap();
+        assert_eq!(deposit.sequence, 2);
+        assert_eq!(deposit.payload.balance(), Some(Money::from_cents(50_000)));
+        let withdrawal = next_event(&mut receiver).await.unwrap();
+        assert_eq!(withdrawal.sequence, 3);
+        assert_eq!(
+            withdrawal.payload.balance(),
+            Some(Money::from_cents(38_000))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lagged_subscriber_skips_missed_events() {
+        let broadcaster = EventBroadcaster::new(1);
+        let mut receiver = broadcaster.subscribe();
+        let events: Vec<EventEnvelope<BankAccount>> = (1..=3)
+            .map(|sequence| {
+                envelope(
+                    sequence,
+                    "2023-03-01T09:00:00+00:00",
+                    BankAccountEvent::CustomerDepositedMoney {
+                        amount: Money::from_cents(1_000),
+                        balance: Money::from_cents(1_000 * sequence as i64),
+                    },
+                )
+            })
+            .collect();
+        broadcaster.dispatch("ACCT-1", &events).await;
+        drop(broadcaster);
+
+        assert_eq!(next_event(&mut receiver).await.unwrap().sequence, 3);
+        assert!(next_event(&mut receiver).await.is_none());
+    }
+
+    #[test]
+    fn test_flow_timing() {
+        let mut view = FlowTimingView::default();
+        view.update(&envelope(
+            1,
+            "2023-03-01T09:00:00+00:00",
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(1_000),
+                balance: Money::ZERO,
+                at: withdrawal_time(),
+            },
+        ));
+        assert_eq!(view.unpaired_withdrawals(), 1);
+        assert_eq!(view.average_holding_time(), None);
+
+        view.update(&envelope(
+            2,
+            "2023-03-01T09:00:00+00:00",
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(20_000),
+                balance: Money::from_cents(20_000),
+            },
+        ));
+        view.update(&envelope(
+            3,
+            "2023-03-02T09:00:00+00:00",
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(5_000),
+                balance: Money::from_cents(15_000),
+                at: withdrawal_time(),
+            },
+        ));
+
+        assert_eq!(view.holding_periods().len(), 1);
+        assert_eq!(view.holding_periods()[0].duration(), Duration::days(1));
+        assert_eq!(view.average_holding_time(), Some(Duration::days(1)));
+    }
+
+    #[test]
+    fn test_sequence_gaps() {
+        let deposit = BankAccountEvent::CustomerDepositedMoney {
+            amount: Money::from_cents(1_000),
+            balance: Money::from_cents(1_000),
+        };
+        let stream = |sequences: &[usize]| -> Vec<EventEnvelope<BankAccount>> {
+            sequences
+                .iter()
+                .map(|sequence| envelope(*sequence, "2023-03-01T09:00:00+00:00", deposit.clone()))
+                .collect()
+        };
+
+        assert!(sequence_gaps(&stream(&[1, 2, 3, 4])).is_empty());
+        assert_eq!(sequence_gaps(&stream(&[1, 2, 4, 5])), vec![3..=3]);
+    }
+
+    #[test]
+    fn test_volatility() {
+        let mut view = VolatilityView::default();
+        for (sequence, time, balance) in [
+            (1, "2023-03-01T09:00:00+00:00", Money::from_cents(5_000)),
+            (2, "2023-03-01T17:00:00+00:00", Money::from_cents(10_000)),
+            (3, "2023-03-02T09:00:00+00:00", Money::from_cents(30_000)),
+            (4, "2023-03-04T09:00:00+00:00", Money::from_cents(20_000)),
+        ] {
+            view.update(&envelope(
+                sequence,
+                time,
+                BankAccountEvent::CustomerDepositedMoney {
+                    amount: Money::from_cents(1_000),
+                    balance,
+                },
+            ));
+        }
+        let day = |day| NaiveDate::from_ymd_opt(2023, 3, day).unwrap();
+
+        // Daily closes of 100, 300, 300 (carried forward) and 200.
+        let volatility = view.volatility(day(1), day(4)).unwrap();
+        assert!((volatility - 82.9156).abs() < 0.001);
+        assert_eq!(view.volatility(day(5), day(5)), Some(0.0));
+        assert_eq!(view.volatility(day(1), day(1)), Some(0.0));
+        assert_eq!(VolatilityView::default().volatility(day(1), day(4)), None);
+    }
+
+    #[test]
+    fn test_time_weighted_return() {
+        let mut view = TimeWeightedReturnView::default();
+        assert_eq!(view.time_weighted_return(), 0.0);
+
+        for (sequence, time, event) in [
+            (
+                1,
+                "2023-01-01T09:00:00+00:00",
+                BankAccountEvent::CustomerDepositedMoney {
+                    amount: Money::from_cents(100_000),
+                    balance: Money::from_cents(100_000),
+                },
+            ),
+            (
+                2,
+                "2023-03-31T09:00:00+00:00",
+                BankAccountEvent::IssuedBonus {
+                    bonus_id: "Q1".to_string(),
+                    amount: Money::from_cents(5_000),
+                    balance: Money::from_cents(105_000),
+                },
+            ),
+            // A large deposit mid-period does not change the return.
+            (
+                3,
+                "2023-05-15T09:00:00+00:00",
+                BankAccountEvent::CustomerDepositedMoney {
+                    amount: Money::from_cents(315_000),
+                    balance: Money::from_cents(420_000),
+                },
+            ),
+            (
+                4,
+                "2023-06-30T09:00:00+00:00",
+                BankAccountEvent::IssuedBonus {
+                    bonus_id: "Q2".to_string(),
+                    amount: Money::from_cents(21_000),
+                    balance: Money::from_cents(441_000),
+                },
+            ),
+        ] {
+            view.update(&envelope(sequence, time, event));
+        }
+
+        // Two sub-periods of 5%.
+        assert!((view.time_weighted_return() - 0.1025).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_time_weighted_return_includes_interest() {
+        let mut view = TimeWeightedReturnView::default();
+        view.update(&envelope(
+            1,
+            "2023-01-01T09:00:00+00:00",
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(100_000),
+                balance: Money::from_cents(100_000),
+            },
+        ));
+        view.update(&envelope(
+            2,
+            "2023-01-31T09:00:00+00:00",
+            BankAccountEvent::InterestAccrued {
+                amount: Money::from_cents(2_000),
+                balance: Money::from_cents(102_000),
+            },
+        ));
+
+        assert!((view.time_weighted_return() - 0.02).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_flagged_transactions() {
+        let mut view = FlaggedTransactionsView::default();
+        for (sequence, event) in [
+            (
+                3,
+                BankAccountEvent::TransactionFlagged {
+                    target_sequence: 1,
+                    reason: "structuring".to_string(),
+                },
+            ),
+            (
+                4,
+                BankAccountEvent::TransactionFlagged {
+                    target_sequence: 2,
+                    reason: "unusual location".to_string(),
+                },
+            ),
+            (5, BankAccountEvent::FlagCleared { target_sequence: 1 }),
+        ] {
+            view.update(&envelope(sequence, "2023-03-01T09:00:00+00:00", event));
+        }
+
+        assert_eq!(
+            view.flagged().iter().collect::<Vec<_>>(),
+            vec![(&2, &"unusual location".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_balance_at_sequence() {
+        let mut view = SequenceBalanceView::default();
+        for (sequence, event) in [
+            (
+                1,
+                BankAccountEvent::AccountOpened {
+                    account_id: "ACCT-1".to_string(),
+                    currency: Some("USD".to_string()),
+                    holder_name: "Jane Doe".to_string(),
+                    email: None,
+                },
+            ),
+            (
+                2,
+                BankAccountEvent::CustomerDepositedMoney {
+                    amount: Money::from_cents(30_000),
+                    balance: Money::from_cents(30_000),
+                },
+            ),
+            (
+                3,
+                BankAccountEvent::CustomerWithdrewCash {
+                    amount: Money::from_cents(5_000),
+                    balance: Money::from_cents(25_000),
+                    at: withdrawal_time(),
+                },
+            ),
+            (4, BankAccountEvent::PaperlessEnrolled),
+            (
+                5,
+                BankAccountEvent::CustomerDepositedMoney {
+                    amount: Money::from_cents(2_500),
+                    balance: Money::from_cents(27_500),
+                },
+            ),
+        ] {
+            view.update(&envelope(sequence, "2023-03-01T09:00:00+00:00", event));
+        }
+
+        assert_eq!(view.balance_at(1), None);
+        assert_eq!(view.balance_at(2), Some(Money::from_cents(30_000)));
+        assert_eq!(view.balance_at(3), Some(Money::from_cents(25_000)));
+        assert_eq!(view.balance_at(4), Some(Money::from_cents(25_000)));
+        assert_eq!(view.balance_at(5), Some(Money::from_cents(27_500)));
+        assert_eq!(view.balance_at(9), Some(Money::from_cents(27_500)));
+    }
+
+    #[test]
+    fn test_fee_burden() {
+        let wire = |amount: Money, fee: Money, balance: Money| BankAccountEvent::WireSent {
+            amount,
+            fee,
+            balance,
+            beneficiary: "ACME Corp".to_string(),
+        };
+        let mut view = FeeBurdenView::default();
+        for (sequence, time, event) in [
+            (
+                1,
+                "2023-03-01T09:00:00+00:00",
+                BankAccountEvent::CustomerDepositedMoney {
+                    amount: Money::from_cents(500_000),
+                    balance: Money::from_cents(500_000),
+                },
+            ),
+            (
+                2,
+                "2023-03-15T09:00:00+00:00",
+                wire(
+                    Money::from_cents(100_000),
+                    Money::from_cents(2_500),
+                    Money::from_cents(397_500),
+                ),
+            ),
+            // A month of fees on a low balance.
+            (
+                3,
+                "2023-04-03T09:00:00+00:00",
+                wire(
+                    Money::from_cents(380_000),
+                    Money::from_cents(2_500),
+                    Money::from_cents(15_000),
+                ),
+            ),
+            (
+                4,
+                "2023-04-20T09:00:00+00:00",
+                wire(
+                    Money::from_cents(5_000),
+                    Money::from_cents(2_500),
+                    Money::from_cents(7_500),
+                ),
+            ),
+        ] {
+            view.update(&envelope(sequence, time, event));
+        }
+
+        // 25 of fees on an average balance of 4487.50.
+        assert!((view.ratio("2023-03").unwrap() - 0.00557).abs() < 0.0001);
+        // 50 of fees on an average balance of 112.50.
+        assert!((view.ratio("2023-04").unwrap() - 0.4444).abs() < 0.0001);
+        assert_eq!(view.months_over(0.05), vec!["2023-04"]);
+        assert_eq!(view.ratio("2023-05"), None);
+    }
+
+    #[test]
+    fn test_goal_projection() {
+        let mut view = GoalProjectionView::default();
+        for (sequence, time, balance) in [
+            (1, "2023-01-05T09:00:00+00:00", Money::from_cents(50_000)),
+            (2, "2023-02-05T09:00:00+00:00", Money::from_cents(100_000)),
+            (3, "2023-03-05T09:00:00+00:00", Money::from_cents(150_000)),
+            (4, "2023-04-05T09:00:00+00:00", Money::from_cents(200_000)),
+        ] {
+            view.update(&envelope(
+                sequence,
+                time,
+                BankAccountEvent::CustomerDepositedMoney {
+                    amount: Money::from_cents(50_000),
+                    balance,
+                },
+            ));
+        }
+
+        // 500.00 a month over the last three months, 3,000.00 to go.
+        assert_eq!(view.average_monthly_inflow(3), Some(500.0));
+        assert_eq!(view.months_to_goal(Money::from_cents(500_000), 3), Some(6));
+        assert_eq!(
+            view.projected_date(Money::from_cents(500_000), 3),
+            NaiveDate::from_ymd_opt(2023, 10, 5)
+        );
+        assert_eq!(view.months_to_goal(Money::from_cents(150_000), 3), Some(0));
+
+        view.update(&envelope(
+            5,
+            "2023-05-05T09:00:00+00:00",
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(200_000),
+                balance: Money::ZERO,
+                at: withdrawal_time(),
+            },
+        ));
+        assert_eq!(view.months_to_goal(Money::from_cents(500_000), 3), None);
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_view_repository() {
+        let repository = InMemoryViewRepository::default();
+        repository
+            .dispatch(
+                "ACCT-1",
+                &[
+                    envelope(
+                        1,
+                        "2023-03-01T09:00:00+00:00",
+                        BankAccountEvent::AccountOpened {
+                            account_id: "ACCT-1".to_string(),
+                            currency: Some("USD".to_string()),
+                            holder_name: "Jane Doe".to_string(),
+                            email: None,
+                        },
+                    ),
+                    envelope(
+                        2,
+                        "2023-03-01T09:05:00+00:00",
+                        BankAccountEvent::CustomerDepositedMoney {
+                            amount: Money::from_cents(50_000),
+                            balance: Money::from_cents(50_000),
+                        },
+                    ),
+                ],
+            )
+            .await;
+        repository
+            .dispatch(
+                "ACCT-1",
+                &[envelope(
+                    3,
+                    "2023-03-01T09:10:00+00:00",
+                    BankAccountEvent::CustomerWroteCheck {
+                        check_number: CheckNumber::new("1170").unwrap(),
+                        amount: Money::from_cents(12_000),
+                        balance: Money::from_cents(38_000),
+                        payee: None,
+                    },
+                )],
+            )
+            .await;
+
+        let view = repository.load_view("ACCT-1").unwrap();
+        assert_eq!(view.account_id(), Some("ACCT-1"));
+        assert_eq!(view.balance(), Money::from_cents(38_000));
+        assert_eq!(view.written_checks(), [CheckNumber::new("1170").unwrap()]);
+        assert_eq!(view.currency(), "USD");
+        assert!(repository.load_view("ACCT-2").is_none());
+    }
+
+    #[test]
+    fn test_view_stored_before_loans_were_recorded() {
+        let mut stored = serde_json::to_value(BankAccountView::default()).unwrap();
+        stored.as_object_mut().unwrap().remove("disbursed_loans");
+
+        let view: BankAccountView = serde_json::from_value(stored).unwrap();
+        assert!(view.disbursed_loans.is_empty());
+    }
+
+    #[test]
+    fn test_stored_view_skips_events_already_applied() {
+        let deposit = |amount: Money, balance: Money| BankAccountEvent::CustomerDepositedMoney {
+            amount,
+            balance,
+        };
+        let mut view = BankAccountView::default();
+        view.update(&envelope(
+            1,
+            "2023-03-01T09:00:00+00:00",
+            deposit(Money::from_cents(1_000), Money::from_cents(1_000)),
+        ));
+
+        // Stored and loaded again, e.g., across a restart, before the event is
+        // dispatched a second time.
+        let stored = serde_json::to_value(&view).unwrap();
+        let mut view: BankAccountView = serde_json::from_value(stored).unwrap();
+        view.update(&envelope(
+            1,
+            "2023-03-01T09:00:00+00:00",
+            deposit(Money::from_cents(1_000), Money::from_cents(1_000)),
+        ));
+        view.update(&envelope(
+            2,
+            "2023-03-01T09:01:00+00:00",
+            deposit(Money::from_cents(500), Money::from_cents(1_500)),
+        ));
+
+        assert_eq!(view.balance(), Money::from_cents(1_500));
+        assert_eq!(view.ledger.len(), 2);
+    }
+
+    #[test]
+    fn test_voided_check_is_removed_from_view() {
+        let mut view = BankAccountView::default();
+        for (sequence, check_number) in [(1, "1170"), (2, "1171")] {
+            view.update(&envelope(
+                sequence,
+                "2023-03-01T09:00:00+00:00",
+                BankAccountEvent::CustomerWroteCheck {
+                    check_number: CheckNumber::new(check_number).unwrap(),
+                    amount: Money::from_cents(10_000),
+                    balance: Money::from_cents(90_000),
+                    payee: None,
+                },
+            ));
+        }
+        view.update(&envelope(
+            3,
+            "2023-03-01T10:00:00+00:00",
+            BankAccountEvent::CheckVoided {
+                check_number: CheckNumber::new("1170").unwrap(),
+                amount: Money::from_cents(10_000),
+                balance: Money::from_cents(100_000),
+            },
+        ));
+
+        assert_eq!(view.written_checks(), [CheckNumber::new("1171").unwrap()]);
+        assert_eq!(view.balance(), Money::from_cents(100_000));
+    }
+
+    #[test]
+    fn test_check_deposits_leave_pending_when_cleared_or_rejected() {
+        let mut view = BankAccountView::default();
+        let deposits = [
+            (1, "images/1170-front.png", 10_000),
+            (2, "images/1171-front.png", 20_000),
+        ];
+        for (sequence, front_image_ref, balance) in deposits {
+            view.update(&envelope(
+                sequence,
+                "2023-03-01T09:00:00+00:00",
+                BankAccountEvent::CheckImageDeposited {
+                    amount: Money::from_cents(10_000),
+                    balance: Money::from_cents(balance),
+                    front_image_ref: front_image_ref.to_string(),
+                    back_image_ref: "images/back.png".to_string(),
+                },
+            ));
+        }
+        assert_eq!(view.available_balance(), Money::ZERO);
+
+        view.update(&envelope(
+            3,
+            "2023-03-02T09:00:00+00:00",
+            BankAccountEvent::CheckDepositCleared {
+                front_image_ref: "images/1170-front.png".to_string(),
+                amount: Money::from_cents(10_000),
+            },
+        ));
+        view.update(&envelope(
+            4,
+            "2023-03-02T09:00:00+00:00",
+            BankAccountEvent::CheckDepositRejected {
+                front_image_ref: "images/1171-front.png".to_string(),
+                amount: Money::from_cents(10_000),
+                balance: Money::from_cents(10_000),
+                reason: "signature missing".to_string(),
+            },
+        ));
+
+        assert_eq!(view.balance(), Money::from_cents(10_000));
+        assert_eq!(view.available_balance(), Money::from_cents(10_000));
+    }
+
+    #[test]
+    fn test_transaction_history() {
+        let mut view = TransactionHistoryView::default();
+        view.update(&envelope(
+            1,
+            "2023-03-01T09:00:00+00:00",
+            BankAccountEvent::AccountOpened {
+                account_id: "ACCT-1".to_string(),
+                currency: Some("USD".to_string()),
+                holder_name: "Jane Doe".to_string(),
+                email: None,
+            },
+        ));
+        view.update(&envelope(
+            2,
+            "2023-03-01T09:05:00+00:00",
+            BankAccountEvent::CustomerDepositedMoney {
+                amount: Money::from_cents(50_000),
+                balance: Money::from_cents(50_000),
+            },
+        ));
+        // Delivered out of order.
+        view.update(&envelope(
+            4,
+            "2023-03-01T11:00:00+00:00",
+            BankAccountEvent::CustomerWroteCheck {
+                check_number: CheckNumber::new("1170").unwrap(),
+                amount: Money::from_cents(25_000),
+                balance: Money::from_cents(13_000),
+                payee: None,
+            },
+        ));
+        view.update(&envelope(
+            3,
+            "2023-03-01T10:00:00+00:00",
+            BankAccountEvent::CustomerWithdrewCash {
+                amount: Money::from_cents(12_000),
+                balance: Money::from_cents(38_000),
+                at: withdrawal_time(),
+            },
+        ));
+
+        let record = |kind: &str, amount, balance_after, sequence| TransactionRecord {
+            kind: kind.to_string(),
+            amount: Money::from_cents(amount),
+            balance_after: Money::from_cents(balance_after),
+            sequence,
+        };
+        assert_eq!(
+            view.history(),
+            [
+                record("deposit", 50_000, 50_000, 2),
+                record("withdrawal", 12_000, 38_000, 3),
+                record("check", 25_000, 13_000, 4),
+            ]
+        );
+    }
+}
+
+// These tests require a running EventStore instance (`docker-compose up -d`),
+// run them with `cargo test --features eventstore-integration`.
+#[cfg(all(test, feature = "eventstore-integration"))]
+mod rebuild_view_tests {
+    use cqrs_es::persist::GenericQuery;
+    use cqrs_es::CqrsFramework;
+    use std::sync::Arc;
+
+    use crate::domain::aggregate::BankAccount;
+    use crate::domain::commands::BankAccountCommand;
+    use crate::domain::money::Money;
+    use crate::eventstore_client::get_client;
+    use crate::persistence::eventstore_store::EventStoreDbStore;
+    use crate::queries::{rebuild_view, BankAccountView, InMemoryViewRepository};
+    use crate::services::{BankAccountServices, HappyPathBankAccountServices};
+
+    fn store() -> EventStoreDbStore<BankAccount> {
+        EventStoreDbStore::new(get_client().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_rebuilt_view_matches_live_view() {
+        let account_id = format!("rebuild-test-{}", chrono::Utc::now().timestamp_nanos());
+        let repository = Arc::new(InMemoryViewRepository::default());
+        let account_query: GenericQuery<InMemoryViewRepository, BankAccountView, BankAccount> =
+            GenericQuery::new(repository.clone());
+        let services = BankAccountServices::new(Box::new(HappyPathBankAccountServices));
+        let cqrs = CqrsFramework::new(store(), vec![Box::new(account_query)], services);
+        let commands = vec![
+            BankAccountCommand::OpenAccount {
+                account_id: account_id.clone(),
+                currency: "USD".to_string(),
+                holder_name: "Jane Doe".to_string(),
+                email: None,
+            },
+            BankAccountCommand::DepositMoney {
+                amount: Money::from_cents(50_000),
+                currency: "USD".to_string(),
+            },
+            BankAccountCommand::WithdrawMoney {
+                amount: Money::from_cents(12_000),
+                atm_id: "ATM34f1ba3c".to_string(),
+                authorized_by: None,
+                currency: "USD".to_string(),
+            },
+        ];
+        for command in commands {
+            cqrs.execute(&account_id, command).await.unwrap();
+        }
+
+        let rebuilt: BankAccountView = rebuild_view(&store(), &account_id).await.unwrap();
+        let live = repository.load_view(&account_id).unwrap();
+        assert_eq!(
+            serde_json::to_value(&rebuilt).unwrap(),
+            serde_json::to_value(&live).unwrap()
+        );
+        assert_eq!(rebuilt.balance(), Money::from_cents(38_000));
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_view_of_empty_stream() {
+        let account_id = format!("rebuild-test-{}", chrono::Utc::now().timestamp_nanos());
+        let rebuilt: BankAccountView = rebuild_view(&store(), &account_id).await.unwrap();
+        assert_eq!(
+            serde_json::to_value(&rebuilt).unwrap(),
+            serde_json::to_value(BankAccountView::default()).unwrap()
+        );
+    }
+}
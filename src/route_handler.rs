@@ -1,10 +1,16 @@
+use crate::api_error::ApiError;
+use crate::book_transfer::book_transfer;
 use crate::command_extractor::CommandExtractor;
+use crate::domain::commands::BankAccountCommand;
+use crate::domain::errors::BankAccountError;
+use crate::queries::BalanceResponse;
 use crate::state::ApplicationState;
 use axum::extract::{Path, State};
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use cqrs_es::persist::ViewRepository;
+use cqrs_es::AggregateError;
 
 // Serves as our query endpoint to respond with the materialized `BankAccountView`
 // for the requested account.
@@ -25,21 +31,67 @@ pub async fn query_handler(
     }
 }
 
+// Responds with just the balance details of the requested account.
+pub async fn balance_handler(
+    Path(account_id): Path<String>,
+    State(state): State<ApplicationState>,
+) -> Response {
+    let view = match state.account_query.load(&account_id).await {
+        Ok(view) => view,
+        Err(err) => {
+            println!("Error: {:#?}\n", err);
+            return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response();
+        }
+    };
+    match view {
+        None => StatusCode::NOT_FOUND.into_response(),
+        Some(account_view) => {
+            (StatusCode::OK, Json(BalanceResponse::from(&account_view))).into_response()
+        }
+    }
+}
+
 // Serves as our command endpoint to make changes in a `BankAccount` aggregate.
+// A book transfer also changes the destination account so is coordinated
+// by `book_transfer` rather than executed directly, and the steps it issues to
+// the destination account are not accepted from clients.
 pub async fn command_handler(
     Path(account_id): Path<String>,
     State(state): State<ApplicationState>,
     CommandExtractor(metadata, command): CommandExtractor,
 ) -> Response {
-    match state
-        .cqrs
-        .execute_with_metadata(&account_id, command, metadata)
-        .await
-    {
+    if command.is_book_transfer_step() {
+        let err = AggregateError::UserError(BankAccountError::from("command not permitted"));
+        return ApiError::from(err).into_response();
+    }
+    let result = match command {
+        BankAccountCommand::BookTransfer {
+            to_account_id,
+            amount,
+            currency,
+        } => {
+            book_transfer(
+                &state.cqrs,
+                &account_id,
+                to_account_id,
+                amount,
+                currency,
+                metadata,
+            )
+            .await
+        }
+        command => {
+            state
+                .cqrs
+                .execute_with_metadata(&account_id, command, metadata)
+                .await
+        }
+    };
+    match result {
         Ok(_) => StatusCode::NO_CONTENT.into_response(),
-        Err(err) =>  {
+        Err(err) => {
             println!("Error: {:#?}\n", err);
-            (StatusCode::BAD_REQUEST, err.to_string()).into_response()
-        },
+            ApiError::from(err).into_response()
+        }
     }
 }
@@ -1,19 +1,52 @@
 use async_trait::async_trait;
+use chrono::{DateTime, NaiveTime, Utc};
+
+use crate::domain::money::Money;
 
 pub struct BankAccountServices {
     pub services: Box<dyn BankAccountApi>,
+    // The source of the current time for any events that record when they occurred,
+    // this may be replaced to make time-dependent logic testable.
+    pub clock: fn() -> DateTime<Utc>,
+    // Outgoing wires must be sent before this time of day (UTC).
+    pub wire_cutoff: NaiveTime,
+    // The most that may be withdrawn as cash in a day (UTC).
+    pub daily_withdrawal_limit: Money,
 }
 
 impl BankAccountServices {
     pub fn new(services: Box<dyn BankAccountApi>) -> Self {
-        Self { services }
+        Self {
+            services,
+            clock: Utc::now,
+            wire_cutoff: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            daily_withdrawal_limit: Money::from_cents(100_000),
+        }
+    }
+
+    pub fn with_clock(self, clock: fn() -> DateTime<Utc>) -> Self {
+        Self { clock, ..self }
+    }
+
+    pub fn with_wire_cutoff(self, wire_cutoff: NaiveTime) -> Self {
+        Self {
+            wire_cutoff,
+            ..self
+        }
+    }
+
+    pub fn with_daily_withdrawal_limit(self, daily_withdrawal_limit: Money) -> Self {
+        Self {
+            daily_withdrawal_limit,
+            ..self
+        }
     }
 }
 
 // External services must be called during the processing of the command.
 #[async_trait]
 pub trait BankAccountApi: Sync + Send {
-    async fn atm_withdrawal(&self, atm_id: &str, amount: f64) -> Result<(), AtmError>;
+    async fn atm_withdrawal(&self, atm_id: &str, amount: Money) -> Result<(), AtmError>;
     async fn validate_check(&self, account_id: &str, check: &str) -> Result<(), CheckingError>;
 }
 pub struct AtmError;
@@ -24,7 +57,7 @@ pub struct HappyPathBankAccountServices;
 
 #[async_trait]
 impl BankAccountApi for HappyPathBankAccountServices {
-    async fn atm_withdrawal(&self, _atm_id: &str, _amount: f64) -> Result<(), AtmError> {
+    async fn atm_withdrawal(&self, _atm_id: &str, _amount: Money) -> Result<(), AtmError> {
         Ok(())
     }
 